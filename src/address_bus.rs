@@ -1,3 +1,4 @@
+use crate::save_state::StateError;
 use rand::prelude::Rng;
 use std::{fs::File, io::Read};
 
@@ -6,9 +7,84 @@ const MEMORY_SIZE: usize = (u16::MAX as usize) + 1;
 pub trait AddressBus {
     fn read(&mut self, address: u16) -> u8;
     fn write(&mut self, address: u16, value: u8);
+
+    // Reads without any side effects, for debuggers and tracers that mustn't disturb the run.
+    // Devices where reading clears a flag or pops a FIFO should override it
+    fn peek(&mut self, address: u16) -> u8 {
+        self.read(address)
+    }
+
+    // State of the RDY line, asked before every read cycle. Returning false halts the CPU for
+    // that cycle (it repeats the read) so a DMA device knows exactly when the CPU stopped
+    fn rdy(&mut self, _cycle: u64) -> bool {
+        true
+    }
+
+    // Appends the device's own state to a save state, in whatever format it likes. Devices
+    // without state (or that don't support save states) can leave these alone
+    fn save_state(&self, _state: &mut Vec<u8>) {}
+
+    // Gets back exactly the bytes save_state() appended
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), StateError> {
+        Ok(())
+    }
 }
 
-struct MemoryBank {
+// Lets a boxed (and possibly dyn) bus be used wherever a bus is expected
+impl<T: AddressBus + ?Sized> AddressBus for Box<T> {
+    fn read(&mut self, address: u16) -> u8 {
+        (**self).read(address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        (**self).write(address, value)
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        (**self).peek(address)
+    }
+
+    fn rdy(&mut self, cycle: u64) -> bool {
+        (**self).rdy(cycle)
+    }
+
+    fn save_state(&self, state: &mut Vec<u8>) {
+        (**self).save_state(state)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        (**self).load_state(state)
+    }
+}
+
+// Lets the CPU run on a bus the caller keeps ownership of
+impl<T: AddressBus + ?Sized> AddressBus for &mut T {
+    fn read(&mut self, address: u16) -> u8 {
+        (**self).read(address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        (**self).write(address, value)
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        (**self).peek(address)
+    }
+
+    fn rdy(&mut self, cycle: u64) -> bool {
+        (**self).rdy(cycle)
+    }
+
+    fn save_state(&self, state: &mut Vec<u8>) {
+        (**self).save_state(state)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        (**self).load_state(state)
+    }
+}
+
+pub struct MemoryBank {
     bytes: [u8; MEMORY_SIZE],
 }
 
@@ -20,6 +96,18 @@ impl AddressBus for MemoryBank {
     fn write(&mut self, address: u16, value: u8) {
         self.bytes[address as usize] = value;
     }
+
+    fn save_state(&self, state: &mut Vec<u8>) {
+        state.extend_from_slice(&self.bytes);
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        if state.len() != MEMORY_SIZE {
+            return Err(StateError::Invalid("memory bank size"));
+        }
+        self.bytes.copy_from_slice(state);
+        Ok(())
+    }
 }
 
 impl MemoryBank {
@@ -35,6 +123,12 @@ impl MemoryBank {
     }
 }
 
+impl Default for MemoryBank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn memory_from_file(file: &mut File, randomize_unfilled_bytes: bool) -> impl AddressBus {
     let mut memory_bank = MemoryBank::new();
     if randomize_unfilled_bytes {
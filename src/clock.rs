@@ -0,0 +1,151 @@
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const MOS6502_FREQUENCY: f64 = 1_000_000.0;
+pub const NES_NTSC_FREQUENCY: f64 = 1_789_773.0;
+pub const NES_PAL_FREQUENCY: f64 = 1_662_607.0;
+
+// Sleeping per cycle is far too coarse so the clock only throttles once every millisecond
+// of emulated time
+const BATCHES_PER_SECOND: f64 = 1000.0;
+
+// If the host falls behind by more than this (a debugger pause, a slow device...) the clock
+// resynchronizes instead of running unthrottled until it has caught up
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ClockMode {
+    #[default]
+    Unthrottled, // As fast as the host can go
+    RealTime(f64),    // Frequency in Hz
+    Scaled(f64, f64), // Frequency in Hz and a speed multiplier (0.5 is half speed)
+}
+
+impl ClockMode {
+    // Emulated cycles per second of host time, None when unthrottled
+    pub fn cycles_per_second(&self) -> Option<f64> {
+        match *self {
+            ClockMode::Unthrottled => None,
+            ClockMode::RealTime(frequency) => Some(frequency),
+            ClockMode::Scaled(frequency, multiplier) => Some(frequency * multiplier),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockError {
+    InvalidFrequency(f64),  // Not a positive, finite number of Hz
+    InvalidMultiplier(f64), // Not a positive, finite speed multiplier
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClockError::InvalidFrequency(frequency) => {
+                write!(f, "Invalid clock frequency {} Hz", frequency)
+            }
+            ClockError::InvalidMultiplier(multiplier) => {
+                write!(f, "Invalid clock speed multiplier {}", multiplier)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClockError {}
+
+fn valid(value: f64) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+pub struct Clock {
+    mode: ClockMode,
+    batch_cycles: u64,
+    pending_cycles: u64,
+    elapsed_cycles: u64,
+    start: Instant,
+}
+
+// Unthrottled
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            mode: ClockMode::Unthrottled,
+            batch_cycles: u64::MAX,
+            pending_cycles: 0,
+            elapsed_cycles: 0,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock {
+    pub fn new(mode: ClockMode) -> Result<Self, ClockError> {
+        match mode {
+            ClockMode::Unthrottled => {}
+            ClockMode::RealTime(frequency) | ClockMode::Scaled(frequency, _)
+                if !valid(frequency) =>
+            {
+                return Err(ClockError::InvalidFrequency(frequency));
+            }
+            ClockMode::Scaled(_, multiplier) if !valid(multiplier) => {
+                return Err(ClockError::InvalidMultiplier(multiplier));
+            }
+            ClockMode::RealTime(_) | ClockMode::Scaled(..) => {}
+        }
+
+        let batch_cycles = match mode.cycles_per_second() {
+            Some(cycles_per_second) => ((cycles_per_second / BATCHES_PER_SECOND) as u64).max(1),
+            None => u64::MAX,
+        };
+
+        Ok(Self {
+            mode,
+            batch_cycles,
+            ..Self::default()
+        })
+    }
+
+    pub fn mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.mode != ClockMode::Unthrottled
+    }
+
+    pub fn tick(&mut self) {
+        if !self.is_throttled() {
+            return;
+        }
+
+        self.pending_cycles += 1;
+        if self.pending_cycles >= self.batch_cycles {
+            self.throttle();
+        }
+    }
+
+    // Forgets about the time spent so far, used when the emulation is resumed after a pause
+    pub fn resync(&mut self) {
+        self.pending_cycles = 0;
+        self.elapsed_cycles = 0;
+        self.start = Instant::now();
+    }
+
+    fn throttle(&mut self) {
+        let Some(cycles_per_second) = self.mode.cycles_per_second() else {
+            return;
+        };
+
+        self.elapsed_cycles += self.pending_cycles;
+        self.pending_cycles = 0;
+
+        let target = Duration::from_secs_f64(self.elapsed_cycles as f64 / cycles_per_second);
+        let elapsed = self.start.elapsed();
+        if target > elapsed {
+            thread::sleep(target - elapsed);
+        } else if elapsed - target > MAX_LAG {
+            self.resync();
+        }
+    }
+}
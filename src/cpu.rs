@@ -1,17 +1,30 @@
 use bitflags::bitflags;
-use std::boxed::Box;
-use std::time::Instant;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::RangeInclusive;
 
 mod arithmetic_instructions;
 mod branching_instructions;
+mod breakpoints;
+mod illegal_instructions;
 mod inc_dec_instructions;
 mod logical_instructions;
 pub mod opcode_modes;
+pub mod opcode_table;
+mod provenance;
+mod rewind;
 mod stack_instructions;
+mod state;
 mod status_instructions;
 mod transfer_load_store_instructions;
 
 use crate::address_bus::AddressBus;
+use crate::clock::{Clock, ClockError, ClockMode};
+use crate::save_state::StateError;
+use breakpoints::Breakpoints;
+use opcode_table::{Handler, Opcode};
+use provenance::Provenance;
+use rewind::Rewind;
 
 pub const NMI_VECTOR: u16 = 0xFFFA;
 pub const RESET_VECTOR: u16 = 0xFFFC;
@@ -20,6 +33,10 @@ pub const BRK_VECTOR: u16 = 0xFFFE;
 
 pub const STACK_BASE: u16 = 0x100;
 
+// Value ORed into A by the unstable XAA and LXA instructions, it differs between chips
+// (and even with temperature) but $EE is the most common
+pub const DEFAULT_MAGIC_CONSTANT: u8 = 0xEE;
+
 bitflags! {
     #[derive(Debug, Clone, Copy)]
     pub struct CPUFLAGS : u8 {
@@ -37,6 +54,37 @@ bitflags! {
     }
 }
 
+#[derive(PartialEq, Debug, Copy, Clone, Default)]
+pub enum CpuVariant {
+    #[default]
+    Nmos6502, // Original MOS 6502 including the undocumented opcodes
+    Ricoh2A03,  // NES/Famicom, NMOS 6502 with the decimal mode cut out
+    Cmos65SC02, // 65C02 instruction set without the Rockwell bit instructions
+    Cmos65C02,  // Rockwell R65C02, adds RMB, SMB, BBR and BBS
+    W65C02S,    // WDC W65C02S, adds WAI and STP
+}
+
+impl CpuVariant {
+    pub fn is_cmos(self) -> bool {
+        matches!(
+            self,
+            CpuVariant::Cmos65SC02 | CpuVariant::Cmos65C02 | CpuVariant::W65C02S
+        )
+    }
+
+    pub fn has_decimal_mode(self) -> bool {
+        self != CpuVariant::Ricoh2A03
+    }
+
+    pub fn has_bit_instructions(self) -> bool {
+        matches!(self, CpuVariant::Cmos65C02 | CpuVariant::W65C02S)
+    }
+
+    pub fn has_wait_and_stop(self) -> bool {
+        self == CpuVariant::W65C02S
+    }
+}
+
 #[derive(Clone)]
 pub struct MOS6502Registers {
     pub pc: u16,      // Program Counter
@@ -60,20 +108,191 @@ impl Default for MOS6502Registers {
     }
 }
 
-pub struct MOS6502 {
+pub struct MOS6502<B: AddressBus> {
     pub reg: MOS6502Registers,
-    pub bus: Box<dyn AddressBus>,
+    pub bus: B,
+
+    variant: CpuVariant,
+    opcodes: [Opcode<B>; 256], // The opcode table of the variant
+    trap_policy: TrapPolicy,
+    trap: Option<Trap>,
+    instruction_address: u16, // Address of the instruction (or interrupt) being executed
+    jammed: bool,
+    waiting: bool, // WAI, until an interrupt arrives
+    stopped: bool, // STP, until a reset
+    magic_constant: u8,
+    undocumented_opcodes: bool, // Whether undocumented opcodes run or are reported as illegal
+    error: Option<CpuError>,    // Set by the running instruction, returned by step()
+    cycles: u64,
+
+    // Where the current instruction (or interrupt) is at, tick_cycle() resumes it from here
+    sequence: Option<Sequence>, // None between instructions
+    instruction_cycle: u8,      // Cycles of the sequence already run
+    sequence_start: u64,        // cycles() when the sequence began
+    address: u16,               // Address latched by the earlier cycles of the instruction
+    base_address: u16,          // The address before indexing
+    pointer: u16,               // Zero page or indirect pointer
+    data: u8,                   // Value latched by an earlier cycle
+    decimal_fixup: bool,        // A 65C02 decimal ADC or SBC still needs its extra cycle
+    interrupt_delayed: bool,    // The current cycle doesn't move sampled interrupts along
+
+    // Interrupt lines, true when asserted
+    irq_line: bool,
+    nmi_line: bool,
+    nmi_detected: bool, // Set on a rising edge of the NMI line until the NMI is serviced
+    interrupt_sampled: bool, // Whether an interrupt was seen at the end of the last cycle
+    interrupt_pending: bool, // Whether an interrupt was seen at the end of the cycle before
 
-    trapped: bool,
-    last_clock: Instant,
+    // SO line, sets V when asserted
+    so_line: bool,
+    so_detected: bool,
+
+    scheduled_lines: Vec<(u64, InputLine, bool)>, // Line changes at a given cycle
+
+    // RDY line
+    stall_cycles: u64, // Cycles RDY is still held low for, starting at the next read
+    halted_at: Option<u64>, // The cycle RDY last halted the CPU
+    halted_cycles: u64,
+    max_halted_cycles: u64,          // In a row, before step() gives up
+    halted: bool,                    // Whether RDY halted the last cycle
+    bus_log: Option<Vec<BusAccess>>, // Every bus access when enabled
+    rewind: Option<Rewind>,          // Snapshots and journal when rewinding is enabled
+    provenance: Option<Provenance>,  // The last writes to every address when enabled
+    breakpoints: Breakpoints,
+    clock: Clock,
+
+    // Whether any of the features above that look at every cycle or bus access is in use.
+    // Worked out once per step so the accesses only check this
+    hooks: bool,
 }
 
+// For systems that pick their bus at runtime, every access goes through a virtual call
+pub type BoxedMOS6502 = MOS6502<Box<dyn AddressBus>>;
+
 pub fn same_page(addr1: u16, addr2: u16) -> bool {
     (addr1 & 0xFF00) == (addr2 & 0xFF00)
 }
 
-#[allow(dead_code)]
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusOperation {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusAccess {
+    pub cycle: u64,
+    pub address: u16,
+    pub data: u8,
+    pub operation: BusOperation,
+}
+
+// Conditions that trap the CPU, usually meaning a test program finished or failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrapPolicy {
+    pub self_jump: bool,   // JMP to itself
+    pub self_branch: bool, // A taken branch to itself
+    pub brk: bool,
+    pub jam: bool,
+    pub stop: bool,                // STP
+    pub exit_address: Option<u16>, // Any write to this address
+    pub pcs: HashSet<u16>,         // PC reaching one of these addresses
+    pub cycle_budget: Option<u64>, // cycles() going past this count
+}
+
+impl Default for TrapPolicy {
+    fn default() -> Self {
+        Self {
+            self_jump: true,
+            self_branch: true,
+            brk: false,
+            jam: true,
+            stop: true,
+            exit_address: None,
+            pcs: HashSet::new(),
+            cycle_budget: None,
+        }
+    }
+}
+
+// Which trap condition fired, address is the instruction that caused it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trap {
+    SelfJump { address: u16 },
+    SelfBranch { address: u16 },
+    Brk { address: u16 },
+    Jam { address: u16 },
+    Stop { address: u16 },
+    ExitWrite { address: u16, value: u8 },
+    Pc { address: u16 },
+    CycleBudget { address: u16, cycles: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    Sp,
+    P,
+}
+
+// Stops run(), watchpoints see every bus access the CPU makes including the dummy ones
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    Pc(u16), // Before the instruction at this address runs
+    Read(RangeInclusive<u16>),
+    Write(RangeInclusive<u16>),
+    Register(Register, u8), // When an instruction changes the register to this value
+}
+
+pub type BreakpointId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakpointHit {
+    pub id: BreakpointId,
+    pub instruction_address: u16, // The instruction that hit it, or is about to run for Pc
+    pub access: Option<BusAccess>, // The access a watchpoint caught
+}
+
+// Why run() returned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+    Breakpoint(BreakpointHit),
+    Trapped(Trap),
+}
+
+// What a cycle of an instruction did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cycle {
+    Next, // The instruction continues on the next cycle
+    Done, // That was its last cycle
+}
+
+// RDY halted the CPU instead of running the cycle, it runs again on the next try
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Halted;
+
+pub type CycleResult = Result<Cycle, Halted>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sequence {
+    Fetch,      // The opcode fetch
+    Opcode(u8), // The cycles after the opcode fetch
+    Interrupt(InterruptType),
+    Jammed,
+    Stopped,
+    Waiting,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputLine {
+    Irq,
+    Nmi,
+    So,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InterruptType {
     Nmi,
     Irq,
@@ -81,94 +300,516 @@ pub enum InterruptType {
     Reset,
 }
 
-#[allow(dead_code)]
-impl MOS6502 {
-    pub fn new(memory: Box<dyn AddressBus>) -> Self {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepOutcome {
+    Instruction(u64), // Cycles taken by the instruction
+    Interrupt(u64),   // Cycles taken by an IRQ or NMI sequence
+    Waiting(u64),     // Idle cycles after WAI
+}
+
+impl StepOutcome {
+    pub fn cycles(&self) -> u64 {
+        match *self {
+            StepOutcome::Instruction(cycles) => cycles,
+            StepOutcome::Interrupt(cycles) => cycles,
+            StepOutcome::Waiting(cycles) => cycles,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CpuError {
+    IllegalOpcode { address: u16, opcode: u8 }, // Only when undocumented opcodes are disabled
+    Jammed { address: u16 },                    // A JAM opcode halted the CPU until a reset
+    Stopped { address: u16 },                   // STP halted the CPU until a reset
+    Halted { address: u16 }, // RDY held the CPU for longer than allowed, step() resumes it
+    Unsupported { address: u16, feature: &'static str },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CpuError::IllegalOpcode { address, opcode } => {
+                write!(f, "Illegal opcode ${:02X} at ${:04X}", opcode, address)
+            }
+            CpuError::Jammed { address } => write!(f, "CPU jammed at ${:04X}", address),
+            CpuError::Stopped { address } => write!(f, "CPU stopped at ${:04X}", address),
+            CpuError::Halted { address } => write!(f, "CPU halted by RDY at ${:04X}", address),
+            CpuError::Unsupported { address, feature } => {
+                write!(f, "Unsupported {} at ${:04X}", feature, address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+
+// How much history is kept for rewinding, both in cycles. A snapshot is a full save state so
+// shorter intervals rewind faster but use more memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewindPolicy {
+    pub snapshot_interval: u64,
+    pub history: u64, // At least this many cycles can be rewound
+}
+
+impl Default for RewindPolicy {
+    fn default() -> Self {
+        Self {
+            snapshot_interval: 10_000,
+            history: 1_000_000,
+        }
+    }
+}
+
+// A bus write kept in the rewind journal or by write tracking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteRecord {
+    pub cycle: u64,
+    pub instruction_address: u16, // The instruction (or interrupt) that wrote it
+    pub opcode: Option<u8>,       // None when an IRQ or NMI sequence wrote it
+    pub address: u16,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewindError {
+    Disabled,
+    OutOfHistory,            // Before the oldest snapshot, or after the current cycle
+    NotFound,                // Nothing in the history matched
+    Diverged { cycle: u64 }, // The replay wrote something else, the bus isn't deterministic
+    State(StateError),       // A snapshot failed to load
+}
+
+impl fmt::Display for RewindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RewindError::Disabled => write!(f, "Rewinding isn't enabled"),
+            RewindError::OutOfHistory => write!(f, "Not in the rewind history"),
+            RewindError::NotFound => write!(f, "No match in the rewind history"),
+            RewindError::Diverged { cycle } => write!(f, "Replay diverged at cycle {}", cycle),
+            RewindError::State(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RewindError {}
+
+impl<B: AddressBus> MOS6502<B> {
+    pub fn new(memory: B) -> Self {
+        Self::with_variant(memory, CpuVariant::default())
+    }
+
+    // Unthrottled
+    pub fn with_variant(memory: B, variant: CpuVariant) -> Self {
         Self {
             bus: memory,
-            last_clock: Instant::now(),
-            trapped: false,
+            clock: Clock::default(),
+            variant,
+            opcodes: opcode_table::opcode_table(variant),
+            trap_policy: TrapPolicy::default(),
+            trap: None,
+            instruction_address: 0,
+            jammed: false,
+            waiting: false,
+            stopped: false,
+            magic_constant: DEFAULT_MAGIC_CONSTANT,
+            undocumented_opcodes: true,
+            error: None,
+            cycles: 0,
+            sequence: None,
+            instruction_cycle: 0,
+            sequence_start: 0,
+            address: 0,
+            base_address: 0,
+            pointer: 0,
+            data: 0,
+            decimal_fixup: false,
+            interrupt_delayed: false,
+            irq_line: false,
+            nmi_line: false,
+            nmi_detected: false,
+            interrupt_sampled: false,
+            interrupt_pending: false,
+            so_line: false,
+            so_detected: false,
+            scheduled_lines: Vec::new(),
+            stall_cycles: 0,
+            halted_at: None,
+            halted_cycles: 0,
+            max_halted_cycles: 1 << 20,
+            halted: false,
+            bus_log: None,
+            rewind: None,
+            provenance: None,
+            breakpoints: Breakpoints::default(),
+            hooks: false,
             reg: MOS6502Registers::default(),
         }
     }
 
-    pub fn interrupt(&mut self, interrupt: InterruptType) {
+    // Fails when the frequency or speed multiplier isn't positive
+    pub fn with_clock(
+        memory: B,
+        variant: CpuVariant,
+        clock: ClockMode,
+    ) -> Result<Self, ClockError> {
+        Ok(Self {
+            clock: Clock::new(clock)?,
+            ..Self::with_variant(memory, variant)
+        })
+    }
+
+    // Runs the interrupt sequence right away, regardless of the I flag. Hardware interrupts
+    // should go through set_irq() and set_nmi() instead. An instruction tick_cycle() left
+    // unfinished is abandoned
+    pub fn interrupt(&mut self, interrupt: InterruptType) -> Result<(), CpuError> {
         if interrupt == InterruptType::Reset {
+            self.jammed = false;
+            self.stopped = false;
+            self.waiting = false;
+            self.nmi_detected = false;
+            self.interrupt_sampled = false;
+            self.interrupt_pending = false;
+            self.decimal_fixup = false;
             self.reg.sp = 0x00;
             self.set(CPUFLAGS::ZERO, true);
-            // T1
-            self.stack_peek();
-            self.stack_push_no_read();
-            self.tick();
+        } else {
+            self.waiting = false;
+        }
+
+        self.sequence = Some(Sequence::Interrupt(interrupt));
+        self.instruction_cycle = 0;
+        self.run_sequence()?;
+        self.end_sequence();
+        self.restart_rewind();
+        Ok(())
+    }
+
+    fn reset_cycle(&mut self) -> CycleResult {
+        match self.instruction_cycle {
+            // T1, T2, T3
+            0..=2 => {
+                self.stack_peek()?;
+                self.stack_push_no_read();
+                if self.instruction_cycle == 2 {
+                    self.set(CPUFLAGS::INT_DISABLE, true);
+                    if self.is_cmos() {
+                        self.set(CPUFLAGS::DECIMAL, false);
+                    }
+                }
+                self.tick();
+            }
+            // T4
+            3 => {
+                self.data = self.read(RESET_VECTOR)?;
+                self.tick();
+            }
+            // T5
+            _ => {
+                let address = self.data as u16 | ((self.read(RESET_VECTOR + 1)? as u16) << 8);
+                self.set(CPUFLAGS::BREAK, true);
+                self.reg.pc = address;
+                self.tick();
+                return Ok(Cycle::Done);
+            }
+        }
+        Ok(Cycle::Next)
+    }
+
+    // BRK shares this from the signature byte fetch on, after its own opcode fetch
+    fn interrupt_cycle(&mut self, interrupt: InterruptType) -> CycleResult {
+        match self.instruction_cycle {
+            // T1, skips the signature byte following the BRK opcode
+            1 if interrupt == InterruptType::Brk => {
+                self.read(self.reg.pc)?;
+                self.reg.pc = self.reg.pc.wrapping_add(1);
+                self.tick();
+            }
+            // T0, T1 a hardware interrupt fetches the next opcode twice but never executes it
+            0 | 1 => {
+                self.read(self.reg.pc)?;
+                self.tick();
+            }
+            // Pushes PC and Status
             // T2
-            self.stack_peek();
-            self.stack_push_no_read();
-            self.tick();
+            2 => {
+                self.stack_push((self.reg.pc >> 8) as u8);
+                self.tick();
+            }
             // T3
-            self.set(CPUFLAGS::INT_DISABLE, true);
-            self.stack_peek();
-            self.stack_push_no_read();
-            self.tick();
+            3 => {
+                self.stack_push(self.reg.pc as u8);
+                self.tick();
+            }
             // T4
-            let address = self.read(RESET_VECTOR) as u16;
-            self.tick();
+            4 => {
+                let vector = match interrupt {
+                    InterruptType::Brk => BRK_VECTOR,
+                    InterruptType::Irq => IRQ_VECTOR,
+                    InterruptType::Nmi => NMI_VECTOR,
+                    InterruptType::Reset => RESET_VECTOR,
+                };
+
+                // On NMOS parts an NMI seen while BRK or an IRQ is still pushing hijacks the
+                // vector, the pushed B flag stays as is and the NMI is lost as a separate
+                // interrupt
+                self.address = if !self.is_cmos() && vector == IRQ_VECTOR && self.nmi_detected {
+                    self.nmi_detected = false;
+                    NMI_VECTOR
+                } else {
+                    vector
+                };
+
+                // The B flag only exists on the stack, it is set by BRK and PHP but not by IRQ
+                // and NMI
+                let mut flags = self.reg.ps | CPUFLAGS::UNUSED;
+                flags.set(CPUFLAGS::BREAK, interrupt == InterruptType::Brk);
+                self.stack_push(flags.bits());
+                self.set(CPUFLAGS::INT_DISABLE, true);
+                if self.is_cmos() {
+                    self.set(CPUFLAGS::DECIMAL, false);
+                }
+                self.tick();
+            }
             // T5
-            let address = address | ((self.read(RESET_VECTOR + 1) as u16) << 8);
-            self.set(CPUFLAGS::BREAK, true);
-            self.reg.pc = address;
-            self.tick();
+            5 => {
+                self.data = self.read(self.address)?;
+                self.tick();
+            }
+            // T6
+            _ => {
+                let high = self.read(self.address.wrapping_add(1))?;
+                self.reg.pc = self.data as u16 | (high as u16) << 8;
+                self.tick();
+
+                // The first instruction of the handler always runs before another interrupt
+                self.interrupt_sampled = false;
+                self.interrupt_pending = false;
+                return Ok(Cycle::Done);
+            }
+        }
+        Ok(Cycle::Next)
+    }
+
+    // The IRQ line is level triggered, an interrupt is taken as long as it is asserted
+    // and the I flag is clear
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
 
-            return;
+    // The NMI line is edge triggered, only asserting it again after releasing it
+    // causes another interrupt
+    pub fn set_nmi(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_detected = true;
         }
+        self.nmi_line = asserted;
+    }
 
-        // T1
-        self.read(self.reg.pc);
-        self.reg.pc += 1;
-        self.tick();
+    // The SO (set overflow) pin sets V when it is asserted (on the falling edge of the
+    // active low pin), holding it asserted does nothing more
+    pub fn set_so(&mut self, asserted: bool) {
+        if asserted && !self.so_line {
+            self.so_detected = true;
+        }
+        self.so_line = asserted;
+    }
 
-        // Pushes PC and Status
-        // T2
-        self.stack_push((self.reg.pc >> 8) as u8);
-        self.tick();
+    // Changes the IRQ line during the given cycle, as counted by cycles()
+    pub fn schedule_irq(&mut self, cycle: u64, asserted: bool) {
+        self.scheduled_lines.push((cycle, InputLine::Irq, asserted));
+    }
 
-        // T3
-        self.stack_push(self.reg.pc as u8);
-        self.tick();
+    // Changes the NMI line during the given cycle, as counted by cycles()
+    pub fn schedule_nmi(&mut self, cycle: u64, asserted: bool) {
+        self.scheduled_lines.push((cycle, InputLine::Nmi, asserted));
+    }
+
+    // Changes the SO line during the given cycle, as counted by cycles()
+    pub fn schedule_so(&mut self, cycle: u64, asserted: bool) {
+        self.scheduled_lines.push((cycle, InputLine::So, asserted));
+    }
+
+    pub fn irq(&self) -> bool {
+        self.irq_line
+    }
+
+    pub fn nmi(&self) -> bool {
+        self.nmi_line
+    }
 
-        // T4
-        if interrupt == InterruptType::Brk {
-            self.set(CPUFLAGS::BREAK, true);
+    pub fn so(&self) -> bool {
+        self.so_line
+    }
+
+    fn apply_scheduled_lines(&mut self) {
+        let mut i = 0;
+        while i < self.scheduled_lines.len() {
+            let (cycle, line, asserted) = self.scheduled_lines[i];
+            if cycle >= self.cycles {
+                i += 1;
+                continue;
+            }
+
+            self.scheduled_lines.remove(i);
+            match line {
+                InputLine::Irq => self.set_irq(asserted),
+                InputLine::Nmi => self.set_nmi(asserted),
+                InputLine::So => self.set_so(asserted),
+            }
         }
-        let flags = self.reg.ps | CPUFLAGS::UNUSED;
-        self.stack_push(flags.bits());
-        self.set(CPUFLAGS::INT_DISABLE, true);
-        self.tick();
+    }
 
-        let vector = match interrupt {
-            InterruptType::Brk => BRK_VECTOR,
-            InterruptType::Irq => IRQ_VECTOR,
-            InterruptType::Nmi => NMI_VECTOR,
-            InterruptType::Reset => RESET_VECTOR,
-        };
+    // V is set at the end of the cycle the edge is seen, overriding what the instruction
+    // itself did with V during that cycle
+    fn poll_set_overflow(&mut self) {
+        if self.so_detected {
+            self.so_detected = false;
+            self.set(CPUFLAGS::OVERFLOW, true);
+        }
+    }
 
-        // T5
-        let address = self.read(vector) as u16;
-        self.tick();
+    // Interrupts are sampled at the end of every cycle but the CPU only acts on what was seen
+    // up to the second to last cycle of an instruction. This is why CLI, SEI and PLP, which
+    // change I on their last cycle, only take effect after the following instruction
+    fn poll_interrupts(&mut self) {
+        if !self.interrupt_delayed {
+            self.interrupt_pending = self.interrupt_sampled;
+        }
+        self.interrupt_delayed = false;
+        self.interrupt_sampled =
+            self.nmi_detected || (self.irq_line && !self.reg.ps.contains(CPUFLAGS::INT_DISABLE));
+    }
 
-        // T6
-        let address = address | (self.read(vector + 1) as u16) << 8;
-        self.reg.pc = address;
+    fn read(&mut self, address: u16) -> Result<u8, Halted> {
+        if self.hooks {
+            return self.hooked_read(address);
+        }
+
+        // RDY is ignored during write cycles, the CPU only halts on the next read
+        if !self.bus.rdy(self.cycles) {
+            self.halt(address);
+            return Err(Halted);
+        }
+        self.halted = false;
+        Ok(self.bus.read(address))
+    }
+
+    #[cold]
+    fn hooked_read(&mut self, address: u16) -> Result<u8, Halted> {
+        if self.stall_cycles > 0 || !self.bus.rdy(self.cycles) {
+            self.halt(address);
+            return Err(Halted);
+        }
+        self.halted = false;
+        let data = self.bus_read(address);
+        if self.breakpoints.watching() {
+            self.watch(address, data, BusOperation::Read);
+        }
+        Ok(data)
+    }
+
+    fn bus_read(&mut self, address: u16) -> u8 {
+        let data = self.bus.read(address);
+        self.log_bus_access(address, data, BusOperation::Read);
+        data
+    }
+
+    fn log_bus_access(&mut self, address: u16, data: u8, operation: BusOperation) {
+        if let Some(log) = &mut self.bus_log {
+            log.push(BusAccess {
+                cycle: self.cycles,
+                address,
+                data,
+                operation,
+            });
+        }
+    }
+
+    // Records every bus access, including the dummy reads and writes, until disabled
+    pub fn set_bus_log(&mut self, enabled: bool) {
+        self.bus_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    pub fn bus_log(&self) -> &[BusAccess] {
+        self.bus_log.as_deref().unwrap_or_default()
+    }
+
+    // Returns the accesses logged so far and starts a new log
+    pub fn take_bus_log(&mut self) -> Vec<BusAccess> {
+        self.bus_log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    // The halted CPU repeats the read for a whole cycle, the cycle it was about to run is
+    // tried again afterwards
+    #[cold]
+    fn halt(&mut self, address: u16) {
+        if !self.halted {
+            self.halted_at = Some(self.cycles);
+            self.halted = true;
+        }
+        self.bus_read(address);
+        self.stall_cycles = self.stall_cycles.saturating_sub(1);
+        self.halted_cycles += 1;
         self.tick();
     }
 
-    fn read(&mut self, address: u16) -> u8 {
-        self.bus.read(address)
+    // Holds RDY low for the given number of cycles, like DMA stealing cycles from the CPU
+    pub fn stall(&mut self, cycles: u64) {
+        self.stall_cycles += cycles;
+    }
+
+    pub fn halted_at(&self) -> Option<u64> {
+        self.halted_at
+    }
+
+    // Total cycles the CPU spent halted by RDY
+    pub fn halted_cycles(&self) -> u64 {
+        self.halted_cycles
     }
 
     fn write(&mut self, address: u16, value: u8) {
+        if self.hooks {
+            self.hooked_write(address, value);
+        }
         self.bus.write(address, value);
     }
 
+    #[cold]
+    fn hooked_write(&mut self, address: u16, value: u8) {
+        if self.trap_policy.exit_address == Some(address) {
+            self.trapped(Trap::ExitWrite {
+                address: self.instruction_address,
+                value,
+            });
+        }
+        self.log_bus_access(address, value, BusOperation::Write);
+        if self.breakpoints.watching() {
+            self.watch(address, value, BusOperation::Write);
+        }
+        if self.rewind.is_some() || self.provenance.is_some() {
+            let write = WriteRecord {
+                cycle: self.cycles,
+                instruction_address: self.instruction_address,
+                opcode: match self.sequence {
+                    Some(Sequence::Opcode(opcode)) => Some(opcode),
+                    _ => None,
+                },
+                address,
+                value,
+            };
+            if let Some(rewind) = &mut self.rewind {
+                rewind.record_write(write);
+            }
+            if let Some(provenance) = &mut self.provenance {
+                provenance.record_write(write);
+            }
+        }
+    }
+
     fn set(&mut self, flag: CPUFLAGS, value: bool) {
         self.reg.ps.set(flag, value);
     }
@@ -177,57 +818,140 @@ impl MOS6502 {
         self.reg.ps.intersects(flag)
     }
 
-    fn trapped(&mut self) {
-        self.trapped = true;
+    // Only the first trap is kept until PC is changed with set_pc()
+    fn trapped(&mut self, trap: Trap) {
+        let enabled = match trap {
+            Trap::SelfJump { .. } => self.trap_policy.self_jump,
+            Trap::SelfBranch { .. } => self.trap_policy.self_branch,
+            Trap::Brk { .. } => self.trap_policy.brk,
+            Trap::Jam { .. } => self.trap_policy.jam,
+            Trap::Stop { .. } => self.trap_policy.stop,
+            Trap::ExitWrite { .. } | Trap::Pc { .. } | Trap::CycleBudget { .. } => true,
+        };
+        if enabled && self.trap.is_none() {
+            self.trap = Some(trap);
+        }
     }
 
     pub fn is_trapped(&mut self) -> bool {
-        self.trapped
+        self.trap.is_some()
+    }
+
+    pub fn trap(&self) -> Option<Trap> {
+        self.trap
+    }
+
+    pub fn trap_policy(&self) -> &TrapPolicy {
+        &self.trap_policy
+    }
+
+    pub fn set_trap_policy(&mut self, policy: TrapPolicy) {
+        self.trap_policy = policy;
+    }
+
+    fn jammed(&mut self) {
+        self.jammed = true;
+        self.trapped(Trap::Jam {
+            address: self.instruction_address,
+        });
+    }
+
+    pub fn is_jammed(&self) -> bool {
+        self.jammed
+    }
+
+    fn waiting(&mut self) {
+        self.waiting = true;
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    fn stopped(&mut self) {
+        self.stopped = true;
+        self.trapped(Trap::Stop {
+            address: self.instruction_address,
+        });
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn variant(&self) -> CpuVariant {
+        self.variant
+    }
+
+    fn is_cmos(&self) -> bool {
+        self.variant.is_cmos()
+    }
+
+    fn decimal_enabled(&mut self) -> bool {
+        self.variant.has_decimal_mode() && self.is_set(CPUFLAGS::DECIMAL)
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn clock_mode(&self) -> ClockMode {
+        self.clock.mode()
+    }
+
+    // Call after the emulation was paused so the clock doesn't try to catch up
+    pub fn resync_clock(&mut self) {
+        self.clock.resync();
+    }
+
+    // When disabled undocumented opcodes aren't executed and step() reports them instead
+    pub fn set_undocumented_opcodes(&mut self, enabled: bool) {
+        self.undocumented_opcodes = enabled;
+    }
+
+    // Ends the instruction, step() reports the error at the instruction's address
+    fn unsupported(&mut self, feature: &'static str) -> CycleResult {
+        self.error = Some(CpuError::Unsupported {
+            address: self.instruction_address,
+            feature,
+        });
+        Ok(Cycle::Done)
+    }
+
+    pub fn set_magic_constant(&mut self, value: u8) {
+        self.magic_constant = value;
     }
 
     pub fn set_pc(&mut self, address: u16) {
-        self.trapped = false;
+        self.trap = None;
         self.reg.pc = address;
+        self.restart_rewind();
     }
 
-    pub fn execute(&mut self) {
+    // Runs until the CPU fails to step and returns why
+    pub fn execute(&mut self) -> CpuError {
         loop {
-            self.step();
+            if let Err(error) = self.step() {
+                return error;
+            }
         }
     }
 
-    fn stack_peek(&mut self) -> u8 {
+    fn stack_peek(&mut self) -> Result<u8, Halted> {
         self.read(STACK_BASE + self.reg.sp as u16)
     }
 
-    #[allow(dead_code)]
-    fn stack_write(&mut self, value: u8) {
-        self.write(STACK_BASE + self.reg.sp as u16, value);
-    }
-
     fn stack_pop_no_read(&mut self) {
-        self.reg.sp = unsafe { self.reg.sp.unchecked_add(1) };
+        self.reg.sp = self.reg.sp.wrapping_add(1);
     }
 
-    #[allow(dead_code)]
     fn stack_push_no_read(&mut self) {
-        self.reg.sp = unsafe { self.reg.sp.unchecked_sub(1) };
+        self.reg.sp = self.reg.sp.wrapping_sub(1);
     }
 
     fn stack_push(&mut self, value: u8) {
         self.write(STACK_BASE + self.reg.sp as u16, value);
-        self.reg.sp = unsafe { self.reg.sp.unchecked_sub(1) };
-    }
-
-    #[allow(dead_code)]
-    fn stack_pop(&mut self) -> u8 {
-        self.reg.sp = unsafe { self.reg.sp.unchecked_add(1) };
-        self.read(STACK_BASE + self.reg.sp as u16)
-    }
-
-    fn push_processor_status(&mut self) {
-        let flags = self.reg.ps | CPUFLAGS::UNUSED | CPUFLAGS::BREAK;
-        self.stack_push(flags.bits());
+        self.reg.sp = self.reg.sp.wrapping_sub(1);
     }
 
     fn set_proccessor_status(&mut self, value: u8) {
@@ -235,13 +959,28 @@ impl MOS6502 {
     }
 
     fn tick(&mut self) {
-        loop {
-            let now = Instant::now();
-            let time = now - self.last_clock;
-            if time.subsec_nanos() > 950 {
-                break;
-            }
+        self.cycles += 1;
+        if self.hooks {
+            self.apply_scheduled_lines();
+            self.clock.tick();
         }
+        self.poll_set_overflow();
+        self.poll_interrupts();
+    }
+
+    // The features that need a look at every cycle or bus access, anything else stays off the
+    // hot path. Nothing turns one on in the middle of a step, so once per step is enough
+    fn update_hooks(&mut self) {
+        self.hooks = self.bus_log.is_some()
+            || self.breakpoints.watching()
+            || self.rewind.is_some()
+            || self.provenance.is_some()
+            || !self.scheduled_lines.is_empty()
+            || self.stall_cycles > 0
+            || self.clock.is_throttled()
+            || self.trap_policy.exit_address.is_some()
+            || !self.trap_policy.pcs.is_empty()
+            || self.trap_policy.cycle_budget.is_some();
     }
 
     fn set_zn(&mut self, value: u8) {
@@ -249,156 +988,229 @@ impl MOS6502 {
         self.set(CPUFLAGS::NEGATIVE, (value & (1 << 7)) != 0);
     }
 
-    pub fn step(&mut self) {
-        use arithmetic_instructions::*;
-        use branching_instructions::*;
-        use inc_dec_instructions::*;
-        use logical_instructions::*;
-        use opcode_modes::*;
-        use stack_instructions::*;
-        use status_instructions::*;
-        use transfer_load_store_instructions::*;
-
-        // T1
-        let opcode: u8 = self.read(self.reg.pc);
-        self.reg.pc = (self.reg.pc as u32 + 1) as u16;
-        self.tick();
-
-        let mode = opcode_modes::get_addressing_mode(opcode);
-        match opcode {
-            0x0 => self.interrupt(InterruptType::Brk), // Custom preface no instruction_implied() used
-            0x40 => instruction_implied(self, &return_from_interrupt),
-            0xEA => (),
+    // Executes a single instruction (or interrupt sequence) and reports how many cycles it took.
+    // An instruction tick_cycle() left unfinished is finished and counted whole
+    pub fn step(&mut self) -> Result<StepOutcome, CpuError> {
+        self.run_sequence()?;
+        self.finish_sequence()
+    }
 
-            // Logical
-            0x29 | 0x25 | 0x35 | 0x2D | 0x3D | 0x39 | 0x21 | 0x31 => {
-                instruction_read(self, mode, &logical_and)
-            }
-            0x49 | 0x45 | 0x55 | 0x4D | 0x5D | 0x59 | 0x41 | 0x51 => {
-                instruction_read(self, mode, &logical_exclusive_or)
-            }
-            0x09 | 0x05 | 0x15 | 0x0D | 0x1D | 0x19 | 0x01 | 0x11 => {
-                instruction_read(self, mode, &logical_inclusive_or);
+    // Runs the rest of the current sequence. Gives up when RDY holds the CPU for more than
+    // max_halted_cycles in a row, the next call picks the sequence up where it stopped
+    fn run_sequence(&mut self) -> Result<(), CpuError> {
+        self.update_hooks();
+        let mut halted_cycles = 0;
+        loop {
+            if self.hooks && self.rewind.is_some() {
+                self.record_rewind();
             }
-            0x24 | 0x2C => instruction_read(self, mode, &logical_bit_test),
 
-            // Shifts
-            0x0A | 0x06 | 0x16 | 0x0E | 0x1E => {
-                instruction_read_move_write(self, mode, &logical_shift_left);
-            }
-            0x4A | 0x46 | 0x56 | 0x4E | 0x5E => {
-                instruction_read_move_write(self, mode, &logical_shift_right);
-            }
-            0x2A | 0x26 | 0x36 | 0x2E | 0x3E => {
-                instruction_read_move_write(self, mode, &logical_rotate_left);
-            }
-            0x6A | 0x66 | 0x76 | 0x6E | 0x7E => {
-                instruction_read_move_write(self, mode, &logical_rotate_right);
+            match self.run_cycle() {
+                Ok(Cycle::Done) => return Ok(()),
+                Ok(Cycle::Next) => halted_cycles = 0,
+                Err(Halted) => self.count_halted_cycle(&mut halted_cycles)?,
             }
 
-            // Arithmetic
-            0x69 | 0x65 | 0x75 | 0x6D | 0x7D | 0x79 | 0x61 | 0x71 => {
-                instruction_read(self, mode, &add_with_carry);
-            }
-            0xE9 | 0xE5 | 0xF5 | 0xED | 0xFD | 0xF9 | 0xE1 | 0xF1 => {
-                instruction_read(self, mode, &sub_with_carry);
-            }
-            0xC9 | 0xC5 | 0xD5 | 0xCD | 0xDD | 0xD9 | 0xC1 | 0xD1 => {
-                instruction_read(self, mode, &compare_ac);
-            }
-            0xE0 | 0xE4 | 0xEC => {
-                instruction_read(self, mode, &compare_ix);
-            }
-            0xC0 | 0xC4 | 0xCC => {
-                instruction_read(self, mode, &compare_iy);
+            // Without rewinding nothing happens between the cycles, so the rest of the
+            // instruction can skip run_cycle()
+            if let (None, Some(Sequence::Opcode(opcode))) = (&self.rewind, self.sequence) {
+                return self.run_opcode(opcode);
             }
+        }
+    }
 
-            // Increment & Decrement Instructions
-            0xE6 | 0xF6 | 0xEE | 0xFE => instruction_read_move_write(self, mode, &inc_memory),
-            0xE8 => {
-                instruction_implied(self, &inc_ix);
-            }
-            0xC8 => {
-                instruction_implied(self, &inc_iy);
+    fn run_opcode(&mut self, opcode: u8) -> Result<(), CpuError> {
+        let opcode = self.opcodes[opcode as usize];
+        let mut halted_cycles = 0;
+        loop {
+            match opcode_table::execute(self, &opcode) {
+                Ok(Cycle::Done) => return Ok(()),
+                Ok(Cycle::Next) => {
+                    self.instruction_cycle += 1;
+                    halted_cycles = 0;
+                }
+                Err(Halted) => self.count_halted_cycle(&mut halted_cycles)?,
             }
+        }
+    }
 
-            0xC6 | 0xD6 | 0xCE | 0xDE => {
-                instruction_read_move_write(self, mode, &dec_memory);
-            }
-            0xCA => {
-                instruction_implied(self, &dec_ix);
-            }
-            0x88 => {
-                instruction_implied(self, &dec_iy);
-            }
+    #[cold]
+    fn count_halted_cycle(&self, halted_cycles: &mut u64) -> Result<(), CpuError> {
+        *halted_cycles += 1;
+        if *halted_cycles > self.max_halted_cycles {
+            return Err(CpuError::Halted {
+                address: self.instruction_address,
+            });
+        }
+        Ok(())
+    }
 
-            // Load/Store instructions
-            0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
-                instruction_read(self, mode, &load_ac);
-            }
+    // How long RDY can hold the CPU before step() and interrupt() give up and return
+    // CpuError::Halted, the default covers any DMA transfer of a real system
+    pub fn set_max_halted_cycles(&mut self, cycles: u64) {
+        self.max_halted_cycles = cycles;
+    }
 
-            0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => {
-                instruction_read(self, mode, &load_ix);
-            }
+    // Runs a single bus cycle and lets other devices run before the next one, the instruction
+    // is resumed from where it was on the next call. Returns what step() would have once the
+    // instruction (or interrupt sequence) completes
+    pub fn tick_cycle(&mut self) -> Result<Option<StepOutcome>, CpuError> {
+        self.update_hooks();
+        if self.rewind.is_some() {
+            self.record_rewind();
+        }
 
-            0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => {
-                instruction_read(self, mode, &load_iy);
-            }
+        let Ok(Cycle::Done) = self.run_cycle() else {
+            return Ok(None);
+        };
+        self.finish_sequence().map(Some)
+    }
 
-            0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
-                instruction_write(self, mode, &store_ac);
-            }
+    fn finish_sequence(&mut self) -> Result<StepOutcome, CpuError> {
+        let outcome = match self.sequence {
+            Some(Sequence::Interrupt(_)) => StepOutcome::Interrupt,
+            Some(Sequence::Waiting) => StepOutcome::Waiting,
+            _ => StepOutcome::Instruction,
+        };
+        let cycles = self.cycles - self.sequence_start;
+        self.end_sequence();
+        if self.hooks {
+            self.check_step_traps();
+        }
+
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
 
-            0x86 | 0x96 | 0x8E => {
-                instruction_write(self, mode, &store_ix);
+        // The halting instruction leaves PC right after its opcode
+        if self.jammed {
+            return Err(CpuError::Jammed {
+                address: self.reg.pc.wrapping_sub(1),
+            });
+        }
+        if self.stopped {
+            return Err(CpuError::Stopped {
+                address: self.reg.pc.wrapping_sub(1),
+            });
+        }
+
+        Ok(outcome(cycles))
+    }
+
+    // Whether tick_cycle() stopped in the middle of an instruction
+    pub fn mid_instruction(&self) -> bool {
+        self.sequence.is_some()
+    }
+
+    // Whether the next step() runs the instruction at PC, rather than finishing one, taking an
+    // interrupt or idling
+    pub fn next_is_instruction(&self) -> bool {
+        self.sequence.is_none()
+            && !self.interrupt_pending
+            && !(self.jammed || self.stopped || self.waiting)
+    }
+
+    fn check_step_traps(&mut self) {
+        if self.trap_policy.pcs.contains(&self.reg.pc) {
+            self.trapped(Trap::Pc {
+                address: self.reg.pc,
+            });
+        }
+        if let Some(budget) = self.trap_policy.cycle_budget {
+            if self.cycles > budget {
+                self.trapped(Trap::CycleBudget {
+                    address: self.instruction_address,
+                    cycles: self.cycles,
+                });
             }
+        }
+    }
 
-            0x84 | 0x94 | 0x8C => {
-                instruction_write(self, mode, &store_iy);
+    fn end_sequence(&mut self) {
+        self.sequence = None;
+        self.instruction_cycle = 0;
+        self.sequence_start = self.cycles;
+    }
+
+    // Picks what runs next once the previous instruction is done
+    fn begin_sequence(&mut self) -> Sequence {
+        self.instruction_address = self.reg.pc;
+        let sequence = if self.jammed {
+            Sequence::Jammed
+        } else if self.stopped {
+            Sequence::Stopped
+        } else if self.waiting {
+            Sequence::Waiting
+        } else if self.interrupt_pending {
+            if self.nmi_detected {
+                self.nmi_detected = false;
+                Sequence::Interrupt(InterruptType::Nmi)
+            } else {
+                Sequence::Interrupt(InterruptType::Irq)
             }
+        } else {
+            Sequence::Fetch
+        };
+        self.sequence = Some(sequence);
+        sequence
+    }
 
-            // Transfer instructions
-            0xAA => instruction_implied(self, &transfer_ac_to_x),
-            0xA8 => instruction_implied(self, &transfer_ac_to_y),
-            0x8A => instruction_implied(self, &transfer_x_to_ac),
-            0x98 => instruction_implied(self, &transfer_y_to_ac),
-
-            // Jumps & Calls
-            0x4C => jmp_absolute(self),
-            0x6C => jmp_indirect(self),
-            0x20 => instruction_implied(self, &jump_to_subroutine),
-            0x60 => instruction_implied(self, &return_from_subroutine),
-
-            // Branches
-            0x90 => instruction_read(self, mode, &branch_if_carry_clear),
-            0xB0 => instruction_read(self, mode, &branch_if_carry_set),
-            0xF0 => instruction_read(self, mode, &branch_if_equal),
-            0xD0 => instruction_read(self, mode, &branch_if_not_equal),
-            0x30 => instruction_read(self, mode, &branch_if_minus),
-            0x10 => instruction_read(self, mode, &branch_if_positive),
-            0x50 => instruction_read(self, mode, &branch_if_overflow_clear),
-            0x70 => instruction_read(self, mode, &branch_if_overflow_set),
-
-            // Stack instructions
-            0xBA => instruction_implied(self, &transfer_sp_to_x),
-            0x9A => instruction_implied(self, &transfer_x_to_sp),
-            0x48 => instruction_implied(self, &push_ac),
-            0x08 => instruction_implied(self, &push_processor),
-            0x68 => instruction_implied(self, &pull_ac),
-            0x28 => instruction_implied(self, &pull_processor_status),
-
-            // Status Flags
-            0x18 => instruction_implied(self, &clear_carry),
-            0xD8 => instruction_implied(self, &clear_decimal),
-            0x58 => instruction_implied(self, &clear_int_disable),
-            0xB8 => instruction_implied(self, &clear_overflow),
-            0x38 => instruction_implied(self, &set_carry),
-            0xF8 => instruction_implied(self, &set_decimal),
-            0x78 => instruction_implied(self, &set_int_disable),
+    fn run_cycle(&mut self) -> CycleResult {
+        let sequence = match self.sequence {
+            Some(sequence) => sequence,
+            None => self.begin_sequence(),
+        };
 
-            _ => {
-                panic!("Illegal instruction {:x} at {:x}", opcode, self.reg.pc - 1);
+        let cycle = match sequence {
+            Sequence::Fetch => self.fetch_opcode()?,
+            Sequence::Opcode(opcode) => {
+                let opcode = self.opcodes[opcode as usize];
+                opcode_table::execute(self, &opcode)?
+            }
+            Sequence::Interrupt(InterruptType::Reset) => self.reset_cycle()?,
+            Sequence::Interrupt(interrupt) => self.interrupt_cycle(interrupt)?,
+            Sequence::Jammed => {
+                self.read(0xFFFF)?;
+                self.tick();
+                Cycle::Done
+            }
+            Sequence::Stopped => {
+                self.tick();
+                Cycle::Done
+            }
+            Sequence::Waiting => {
+                // WAI resumes on any interrupt, even a masked IRQ which then just continues
+                self.tick();
+                if self.irq_line || self.nmi_detected {
+                    self.waiting = false;
+                    self.interrupt_pending = self.interrupt_sampled;
+                }
+                Cycle::Done
             }
         };
+
+        self.instruction_cycle += 1;
+        Ok(cycle)
+    }
+
+    fn fetch_opcode(&mut self) -> CycleResult {
+        // T0
+        let address = self.reg.pc;
+        let opcode = self.read(self.reg.pc)?;
+        self.reg.pc = (self.reg.pc as u32 + 1) as u16;
+        self.tick();
+
+        if !self.undocumented_opcodes && !opcode_modes::is_documented(opcode, self.variant) {
+            // Leaves PC on the opcode so the caller can decide to skip it
+            self.reg.pc = address;
+            self.error = Some(CpuError::IllegalOpcode { address, opcode });
+            return Ok(Cycle::Done);
+        }
+
+        if let Handler::FetchOnly = self.opcodes[opcode as usize].handler {
+            return Ok(Cycle::Done);
+        }
+        self.sequence = Some(Sequence::Opcode(opcode));
+        Ok(Cycle::Next)
     }
 }
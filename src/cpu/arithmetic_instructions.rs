@@ -1,7 +1,10 @@
 use crate::cpu::*;
 
-pub fn add_with_carry(cpu: &mut MOS6502, value: u8) {
-    assert!(!cpu.is_set(CPUFLAGS::DECIMAL), "Decimal mode not supported");
+pub fn add_with_carry<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    if cpu.decimal_enabled() {
+        decimal_add_with_carry(cpu, value);
+        return;
+    }
 
     let carry = cpu.is_set(CPUFLAGS::CARRY);
     // A,Z,C,N,V = A+M+C
@@ -16,8 +19,11 @@ pub fn add_with_carry(cpu: &mut MOS6502, value: u8) {
     cpu.reg.ac = result;
 }
 
-pub fn sub_with_carry(cpu: &mut MOS6502, value: u8) {
-    assert!(!cpu.is_set(CPUFLAGS::DECIMAL), "Decimal mode not supported");
+pub fn sub_with_carry<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    if cpu.decimal_enabled() {
+        decimal_sub_with_carry(cpu, value);
+        return;
+    }
 
     let carry = cpu.is_set(CPUFLAGS::CARRY);
     // A,Z,C,N = A-M-(1-C)
@@ -32,24 +38,118 @@ pub fn sub_with_carry(cpu: &mut MOS6502, value: u8) {
     cpu.reg.ac = result;
 }
 
-pub fn compare_ac(cpu: &mut MOS6502, value: u8) {
+// See http://www.6502.org/tutorials/decimal_mode.html
+// The NMOS 6502 computes N and V from the intermediate result after the low nibble
+// has been adjusted but before the high nibble is, and Z from the plain binary sum.
+// Invalid BCD operands (nibbles A-F) are not rejected, they simply go through the
+// same adjustment as the real chip which gives the well known "garbage" results.
+// The 65C02 fixes N and Z to match the accumulator at the cost of an extra cycle.
+fn decimal_add_with_carry<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let ac = cpu.reg.ac as u16;
+    let value = value as u16;
+    let carry = cpu.is_set(CPUFLAGS::CARRY) as u16;
+
+    let binary_result = (ac + value + carry) as u8;
+
+    // Low nibble
+    let mut low = (ac & 0x0F) + (value & 0x0F) + carry;
+    let mut high = (ac & 0xF0) + (value & 0xF0);
+    if low > 0x09 {
+        low += 0x06;
+    }
+    if low > 0x0F {
+        high += 0x10;
+    }
+
+    // Flags are taken before the high nibble is adjusted
+    let overflow = ((ac ^ high) & 0x80) != 0 && ((ac ^ value) & 0x80) == 0;
+    cpu.set(CPUFLAGS::OVERFLOW, overflow);
+    cpu.set(CPUFLAGS::NEGATIVE, (high & 0x80) != 0);
+    cpu.set(CPUFLAGS::ZERO, binary_result == 0);
+
+    // High nibble
+    if high > 0x90 {
+        high += 0x60;
+    }
+    cpu.set(CPUFLAGS::CARRY, high > 0xFF);
+    cpu.reg.ac = ((high & 0xF0) | (low & 0x0F)) as u8;
+
+    if cpu.is_cmos() {
+        cpu.set_zn(cpu.reg.ac);
+        cpu.decimal_fixup = true;
+    }
+}
+
+// On the NMOS 6502 all the flags of a decimal SBC are the same as the binary SBC,
+// only the accumulator is adjusted.
+fn decimal_sub_with_carry<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let ac = cpu.reg.ac;
+    let borrow = !cpu.is_set(CPUFLAGS::CARRY);
+
+    let (binary_result, binary_borrow) = ac.borrowing_sub(value, borrow);
+    let overflow = (binary_result ^ ac) & (value ^ ac) & 0x80 != 0;
+    cpu.set(CPUFLAGS::OVERFLOW, overflow);
+    cpu.set(CPUFLAGS::CARRY, !binary_borrow);
+    cpu.set_zn(binary_result);
+
+    if cpu.is_cmos() {
+        // The 65C02 adjusts the whole binary result at once, which only makes a
+        // difference for invalid BCD operands
+        let low = (ac & 0x0F) as i16 - (value & 0x0F) as i16 - borrow as i16;
+        let mut result = binary_result;
+        if binary_borrow {
+            result = result.wrapping_sub(0x60);
+        }
+        if low < 0 {
+            result = result.wrapping_sub(0x06);
+        }
+        cpu.reg.ac = result;
+        cpu.set_zn(result);
+        cpu.decimal_fixup = true;
+        return;
+    }
+
+    // Low nibble
+    let mut low = (ac & 0x0F) as i16 - (value & 0x0F) as i16 - borrow as i16;
+    let mut high = (ac >> 4) as i16 - (value >> 4) as i16;
+    if low < 0 {
+        low -= 0x06;
+        high -= 1;
+    }
+
+    // High nibble
+    if high < 0 {
+        high -= 0x06;
+    }
+    cpu.reg.ac = (((high as u8) << 4) & 0xF0) | (low as u8 & 0x0F);
+}
+
+// The extra cycle after a 65C02 decimal ADC or SBC
+pub fn decimal_fixup_cycle<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    cpu.read(cpu.reg.pc)?;
+    cpu.decimal_fixup = false;
+    cpu.tick();
+    Ok(Cycle::Done)
+}
+
+pub fn compare_ac<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     // println!("CMP {:02X} == {:02X}", cpu.reg.ac, value);
 
-    let subtracted_value = unsafe { cpu.reg.ac.unchecked_sub(value) };
+    let subtracted_value = cpu.reg.ac.wrapping_sub(value);
 
     cpu.set(CPUFLAGS::CARRY, cpu.reg.ac >= value);
     cpu.set_zn(subtracted_value);
 }
 
-pub fn compare_ix(cpu: &mut MOS6502, value: u8) {
-    let subtracted_value = unsafe { cpu.reg.ix.unchecked_sub(value) };
+pub fn compare_ix<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let subtracted_value = cpu.reg.ix.wrapping_sub(value);
 
     cpu.set(CPUFLAGS::CARRY, cpu.reg.ix >= value);
     cpu.set_zn(subtracted_value);
 }
 
-pub fn compare_iy(cpu: &mut MOS6502, value: u8) {
-    let subtracted_value = unsafe { cpu.reg.iy.unchecked_sub(value) };
+pub fn compare_iy<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let subtracted_value = cpu.reg.iy.wrapping_sub(value);
 
     cpu.set(CPUFLAGS::CARRY, cpu.reg.iy >= value);
     cpu.set_zn(subtracted_value);
@@ -1,189 +1,317 @@
+use crate::cpu::opcode_modes::implied_read;
 use crate::cpu::*;
 
-pub fn jmp_absolute(cpu: &mut MOS6502) {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
-    cpu.tick();
+pub fn jmp_absolute<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.address = cpu.read(cpu.reg.pc)? as u16;
+        cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        cpu.tick();
+        return Ok(Cycle::Next);
+    }
 
     // T2
-    let address = address | ((cpu.read(cpu.reg.pc) as u16) << 8);
+    let address = cpu.address | ((cpu.read(cpu.reg.pc)? as u16) << 8);
 
-    if address + 2 == cpu.reg.pc {
-        cpu.trapped();
+    if address == cpu.instruction_address {
+        cpu.trapped(Trap::SelfJump { address });
     }
 
-    cpu.reg.pc += 1;
     cpu.reg.pc = address;
 
-    cpu.tick()
-}
-
-pub fn jmp_indirect(cpu: &mut MOS6502) {
-    // T1
-    let indirect_address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
     cpu.tick();
+    Ok(Cycle::Done)
+}
 
-    // T2
-    let indirect_address = indirect_address | ((cpu.read(cpu.reg.pc) as u16) << 8);
-    cpu.reg.pc += 1;
-    cpu.tick();
-
-    // T3
-    let address = cpu.read(indirect_address) as u16;
-
-    // T4
+pub fn jmp_indirect<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
     // See https://www.nesdev.org/obelisk-6502-guide/reference.html
     // An original 6502 has does not correctly fetch the target address if the indirect
     // vector falls on a page boundary (e.g. $xxFF where xx is any value from $00 to $FF).
     // In this case fetches the LSB from $xxFF as expected but takes the MSB from $xx00.
-    // This is fixed in some later chips like the 65SC02 so for compatibility always
-    // ensure the indirect vector is not at the end of the page.
-
-    // I'm going to use the 65SC02 version in the sake of simplicity
-    let address = address | ((cpu.read(indirect_address + 1) as u16) << 8);
-    cpu.reg.pc = address;
+    // This is fixed in some later chips like the 65SC02 at the cost of an extra cycle.
+    match (cpu.instruction_cycle, cpu.is_cmos()) {
+        // T1
+        (1, _) => {
+            cpu.pointer = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        (2, _) => {
+            cpu.pointer |= (cpu.read(cpu.reg.pc)? as u16) << 8;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T3
+        (3, true) => {
+            cpu.read(cpu.reg.pc.wrapping_sub(1))?;
+        }
+        // T3 or T4
+        (3, false) | (4, true) => {
+            cpu.address = cpu.read(cpu.pointer)? as u16;
+        }
+        // T4 or T5
+        (_, cmos) => {
+            let high_address = if cmos {
+                cpu.pointer.wrapping_add(1)
+            } else {
+                (cpu.pointer & 0xFF00) | (cpu.pointer.wrapping_add(1) & 0x00FF)
+            };
+            cpu.reg.pc = cpu.address | ((cpu.read(high_address)? as u16) << 8);
+            cpu.tick();
+            return Ok(Cycle::Done);
+        }
+    }
     cpu.tick();
+    Ok(Cycle::Next)
 }
 
-pub fn jump_to_subroutine(cpu: &mut MOS6502) {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1; // Only increment by 1 (and the instruction is 3) because we push the next pc - 1
-    cpu.tick();
-
-    // T2
-    cpu.stack_peek();
-    cpu.tick();
-
-    // T3
-    cpu.stack_push((cpu.reg.pc >> 8) as u8);
+pub fn jmp_absolute_indexed_indirect<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.pointer = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.pointer |= (cpu.read(cpu.reg.pc)? as u16) << 8;
+            cpu.pointer = cpu.pointer.wrapping_add(cpu.reg.ix as u16);
+        }
+        // T3
+        3 => {
+            cpu.read(cpu.reg.pc)?;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T4
+        4 => {
+            cpu.address = cpu.read(cpu.pointer)? as u16;
+        }
+        // T5
+        _ => {
+            let high = cpu.read(cpu.pointer.wrapping_add(1))?;
+            cpu.reg.pc = cpu.address | ((high as u16) << 8);
+            cpu.tick();
+            return Ok(Cycle::Done);
+        }
+    }
     cpu.tick();
+    Ok(Cycle::Next)
+}
 
-    // T4
-    cpu.stack_push(cpu.reg.pc as u8);
+pub fn jump_to_subroutine<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.address = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1); // Only increment by 1 (and the instruction is 3) because we push the next pc - 1
+        }
+        // T2
+        2 => {
+            cpu.stack_peek()?;
+        }
+        // T3
+        3 => cpu.stack_push((cpu.reg.pc >> 8) as u8),
+        // T4
+        4 => cpu.stack_push(cpu.reg.pc as u8),
+        // T5
+        _ => {
+            cpu.reg.pc = cpu.address | ((cpu.read(cpu.reg.pc)? as u16) << 8);
+            cpu.tick();
+            return Ok(Cycle::Done);
+        }
+    }
     cpu.tick();
+    Ok(Cycle::Next)
+}
 
-    // T5
-    let address = address | ((cpu.read(cpu.reg.pc) as u16) << 8);
-    cpu.reg.pc = address;
+pub fn return_from_subroutine<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => return implied_read(cpu),
+        // T2
+        2 => {
+            cpu.stack_peek()?;
+            cpu.stack_pop_no_read();
+        }
+        // T3
+        3 => {
+            cpu.address = cpu.stack_peek()? as u16;
+            cpu.stack_pop_no_read();
+        }
+        // T4
+        4 => {
+            cpu.address |= (cpu.stack_peek()? as u16) << 8;
+        }
+        // T5
+        _ => {
+            cpu.read(cpu.address)?;
+            cpu.reg.pc = cpu.address.wrapping_add(1);
+            cpu.tick();
+            return Ok(Cycle::Done);
+        }
+    }
     cpu.tick();
+    Ok(Cycle::Next)
 }
 
-pub fn return_from_subroutine(cpu: &mut MOS6502) {
-    // T1
-    cpu.read(cpu.reg.pc);
-    cpu.tick();
-    // T2
-    cpu.stack_peek();
-    cpu.stack_pop_no_read();
-    cpu.tick();
-    // T3
-    let address = cpu.stack_peek() as u16;
-    cpu.stack_pop_no_read();
-    cpu.tick();
-    // T4
-    let address = address | ((cpu.stack_peek() as u16) << 8);
-    cpu.tick();
-    // T5
-    cpu.read(address);
-    cpu.reg.pc = address + 1;
-    cpu.tick();
+// Runs the cycles of interrupt(), from the signature byte fetch on
+pub fn break_interrupt<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let cycle = cpu.interrupt_cycle(InterruptType::Brk)?;
+    if cpu.instruction_cycle == 1 {
+        cpu.trapped(Trap::Brk {
+            address: cpu.instruction_address,
+        });
+    }
+    Ok(cycle)
 }
 
-pub fn return_from_interrupt(cpu: &mut MOS6502) {
-    // T1
-    cpu.stack_peek();
-    cpu.stack_pop_no_read();
-    cpu.tick();
-    // T2
-    let value = cpu.stack_peek();
-    cpu.set_proccessor_status(value);
-    cpu.stack_pop_no_read();
-    cpu.tick();
-    // T3
-    let address = cpu.stack_peek() as u16;
-    cpu.stack_pop_no_read();
-    cpu.tick();
-    // T4
-    let address = address | ((cpu.stack_peek() as u16) << 8);
-    cpu.reg.pc = address;
+pub fn return_from_interrupt<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => return implied_read(cpu),
+        // T2
+        2 => {
+            cpu.stack_peek()?;
+            cpu.stack_pop_no_read();
+        }
+        // T3
+        3 => {
+            let value = cpu.stack_peek()?;
+            cpu.set_proccessor_status(value);
+            cpu.stack_pop_no_read();
+        }
+        // T4
+        4 => {
+            cpu.address = cpu.stack_peek()? as u16;
+            cpu.stack_pop_no_read();
+        }
+        // T5
+        _ => {
+            cpu.reg.pc = cpu.address | ((cpu.stack_peek()? as u16) << 8);
+            cpu.tick();
+            return Ok(Cycle::Done);
+        }
+    }
     cpu.tick();
+    Ok(Cycle::Next)
 }
 
-pub fn branch(cpu: &mut MOS6502, relative_offset: u8) {
-    // T1
-    cpu.read(cpu.reg.pc);
-    cpu.tick();
+// The cycles of a taken branch, the offset was fetched into cpu.data
+pub fn branch<B: AddressBus>(cpu: &mut MOS6502<B>, cycle: u8) -> CycleResult {
+    if cycle == 1 {
+        // T1
+        cpu.read(cpu.reg.pc)?;
 
-    let old_pc = cpu.reg.pc;
-    let new_pc = if relative_offset & (1 << 7) != 0 {
-        let offset = !(relative_offset - 1);
-        unsafe { cpu.reg.pc.unchecked_sub(offset as u16) }
-    } else {
-        unsafe { cpu.reg.pc.unchecked_add(relative_offset as u16) }
-    };
+        let relative_offset = cpu.data;
+        let old_pc = cpu.reg.pc;
+        let new_pc = if relative_offset & (1 << 7) != 0 {
+            let offset = !(relative_offset - 1);
+            cpu.reg.pc.wrapping_sub(offset as u16)
+        } else {
+            cpu.reg.pc.wrapping_add(relative_offset as u16)
+        };
+
+        cpu.reg.pc = new_pc;
 
-    cpu.reg.pc = new_pc;
+        if !same_page(old_pc, new_pc) {
+            cpu.address = (old_pc & 0xFF00) | (new_pc & 0x00FF);
+            cpu.tick();
+            return Ok(Cycle::Next);
+        }
 
-    if !same_page(cpu.reg.pc, new_pc) {
+        // A taken branch doesn't poll interrupts during this cycle, unless it also crosses a
+        // page an interrupt seen now is delayed until after the next instruction
+        cpu.interrupt_delayed = true;
+        cpu.tick();
+    } else {
         // T2
-        cpu.read((old_pc & 0xFF00) | (new_pc & 0x00FF));
+        cpu.read(cpu.address)?;
         cpu.tick();
     }
 
-    if relative_offset == 0xFE
-    /* -2 in 2's compilement */
-    {
-        cpu.trapped();
+    if cpu.reg.pc == cpu.instruction_address {
+        cpu.trapped(Trap::SelfBranch {
+            address: cpu.reg.pc,
+        });
     }
+    Ok(Cycle::Done)
 }
 
-pub fn branch_if_carry_clear(cpu: &mut MOS6502, relative_address: u8) {
-    if !cpu.is_set(CPUFLAGS::CARRY) {
-        branch(cpu, relative_address);
+// BBR and BBS (Rockwell and WDC 65C02), branches if the bit of the zero page value is reset/set
+pub fn branch_on_bit<B: AddressBus>(cpu: &mut MOS6502<B>, bit: u8, set: bool) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.address = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.data = cpu.read(cpu.address)?;
+        }
+        // T3
+        3 => {
+            cpu.read(cpu.address)?;
+        }
+        // T4
+        4 => {
+            let relative_offset = cpu.read(cpu.reg.pc)?;
+            let taken = ((cpu.data & (1 << bit)) != 0) == set;
+            cpu.data = relative_offset;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+            cpu.tick();
+
+            return if taken {
+                Ok(Cycle::Next)
+            } else {
+                Ok(Cycle::Done)
+            };
+        }
+        cycle => return branch(cpu, cycle - 4),
     }
+    cpu.tick();
+    Ok(Cycle::Next)
 }
 
-pub fn branch_if_carry_set(cpu: &mut MOS6502, relative_address: u8) {
-    if cpu.is_set(CPUFLAGS::CARRY) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_on_bit_reset<const BIT: u8, B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    branch_on_bit(cpu, BIT, false)
 }
 
-pub fn branch_if_equal(cpu: &mut MOS6502, relative_address: u8) {
-    if cpu.is_set(CPUFLAGS::ZERO) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_on_bit_set<const BIT: u8, B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    branch_on_bit(cpu, BIT, true)
 }
 
-pub fn branch_if_not_equal(cpu: &mut MOS6502, relative_address: u8) {
-    if !cpu.is_set(CPUFLAGS::ZERO) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_always<B: AddressBus>(_cpu: &mut MOS6502<B>) -> bool {
+    true
 }
 
-pub fn branch_if_minus(cpu: &mut MOS6502, relative_address: u8) {
-    if cpu.is_set(CPUFLAGS::NEGATIVE) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_if_carry_clear<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    !cpu.is_set(CPUFLAGS::CARRY)
 }
 
-pub fn branch_if_positive(cpu: &mut MOS6502, relative_address: u8) {
-    if !cpu.is_set(CPUFLAGS::NEGATIVE) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_if_carry_set<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    cpu.is_set(CPUFLAGS::CARRY)
 }
 
-pub fn branch_if_overflow_clear(cpu: &mut MOS6502, relative_address: u8) {
-    if !cpu.is_set(CPUFLAGS::OVERFLOW) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_if_equal<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    cpu.is_set(CPUFLAGS::ZERO)
 }
 
-pub fn branch_if_overflow_set(cpu: &mut MOS6502, relative_address: u8) {
-    if cpu.is_set(CPUFLAGS::OVERFLOW) {
-        branch(cpu, relative_address);
-    }
+pub fn branch_if_not_equal<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    !cpu.is_set(CPUFLAGS::ZERO)
+}
+
+pub fn branch_if_minus<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    cpu.is_set(CPUFLAGS::NEGATIVE)
+}
+
+pub fn branch_if_positive<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    !cpu.is_set(CPUFLAGS::NEGATIVE)
+}
+
+pub fn branch_if_overflow_clear<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    !cpu.is_set(CPUFLAGS::OVERFLOW)
+}
+
+pub fn branch_if_overflow_set<B: AddressBus>(cpu: &mut MOS6502<B>) -> bool {
+    cpu.is_set(CPUFLAGS::OVERFLOW)
 }
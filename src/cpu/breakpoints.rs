@@ -0,0 +1,145 @@
+use crate::cpu::*;
+
+#[derive(Default)]
+pub struct Breakpoints {
+    breakpoints: Vec<(BreakpointId, Breakpoint)>,
+    next_id: BreakpointId,
+    watchpoints: bool, // Whether any are Read or Write, checked on every bus access
+    hit: Option<BreakpointHit>, // The first watchpoint hit during the current instruction
+}
+
+impl Breakpoints {
+    pub fn watching(&self) -> bool {
+        self.watchpoints
+    }
+
+    fn update_watchpoints(&mut self) {
+        self.watchpoints = self.breakpoints.iter().any(|(_, breakpoint)| {
+            matches!(breakpoint, Breakpoint::Read(_) | Breakpoint::Write(_))
+        });
+    }
+}
+
+fn register_value(registers: &MOS6502Registers, register: Register) -> u8 {
+    match register {
+        Register::A => registers.ac,
+        Register::X => registers.ix,
+        Register::Y => registers.iy,
+        Register::Sp => registers.sp,
+        Register::P => registers.ps.bits(),
+    }
+}
+
+impl<B: AddressBus> MOS6502<B> {
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> BreakpointId {
+        let id = self.breakpoints.next_id;
+        self.breakpoints.next_id += 1;
+        self.breakpoints.breakpoints.push((id, breakpoint));
+        self.breakpoints.update_watchpoints();
+        id
+    }
+
+    // Returns whether there was a breakpoint with that id
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        let count = self.breakpoints.breakpoints.len();
+        self.breakpoints
+            .breakpoints
+            .retain(|&(other, _)| other != id);
+        self.breakpoints.update_watchpoints();
+        self.breakpoints.breakpoints.len() != count
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.breakpoints.clear();
+        self.breakpoints.update_watchpoints();
+    }
+
+    pub fn breakpoints(&self) -> &[(BreakpointId, Breakpoint)] {
+        &self.breakpoints.breakpoints
+    }
+
+    pub(super) fn watch(&mut self, address: u16, data: u8, operation: BusOperation) {
+        if self.breakpoints.hit.is_some() {
+            return;
+        }
+
+        let hit = self.breakpoints.breakpoints.iter().find(|(_, breakpoint)| {
+            match (breakpoint, operation) {
+                (Breakpoint::Read(range), BusOperation::Read) => range.contains(&address),
+                (Breakpoint::Write(range), BusOperation::Write) => range.contains(&address),
+                _ => false,
+            }
+        });
+        if let Some(&(id, _)) = hit {
+            self.breakpoints.hit = Some(BreakpointHit {
+                id,
+                instruction_address: self.instruction_address,
+                access: Some(BusAccess {
+                    cycle: self.cycles,
+                    address,
+                    data,
+                    operation,
+                }),
+            });
+        }
+    }
+
+    // Steps until a breakpoint hits or the CPU traps. A watchpoint or register breakpoint
+    // stops it after the instruction that hit it, a PC breakpoint before the instruction at
+    // that address, except for the first one so running again continues past it
+    pub fn run(&mut self) -> Result<RunOutcome, CpuError> {
+        self.breakpoints.hit = None;
+        let mut first = true;
+
+        loop {
+            if let Some(trap) = self.trap {
+                return Ok(RunOutcome::Trapped(trap));
+            }
+
+            if !first && self.next_is_instruction() {
+                let pc = self.reg.pc;
+                let hit = self
+                    .breakpoints
+                    .breakpoints
+                    .iter()
+                    .find(|(_, breakpoint)| *breakpoint == Breakpoint::Pc(pc));
+                if let Some(&(id, _)) = hit {
+                    return Ok(RunOutcome::Breakpoint(BreakpointHit {
+                        id,
+                        instruction_address: pc,
+                        access: None,
+                    }));
+                }
+            }
+            first = false;
+
+            // A JAM or STP both traps and errors, the trap takes precedence
+            let before = self.reg.clone();
+            if let Err(error) = self.step() {
+                return match self.trap {
+                    Some(trap) => Ok(RunOutcome::Trapped(trap)),
+                    None => Err(error),
+                };
+            }
+
+            if let Some(hit) = self.breakpoints.hit.take() {
+                return Ok(RunOutcome::Breakpoint(hit));
+            }
+
+            let hit = self.breakpoints.breakpoints.iter().find(|(_, breakpoint)| {
+                let Breakpoint::Register(register, value) = *breakpoint else {
+                    return false;
+                };
+                register_value(&self.reg, register) == value
+                    && register_value(&before, register) != value
+            });
+            if let Some(&(id, _)) = hit {
+                return Ok(RunOutcome::Breakpoint(BreakpointHit {
+                    id,
+                    instruction_address: self.instruction_address,
+                    access: None,
+                }));
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cpu::arithmetic_instructions::*;
+use crate::cpu::logical_instructions::*;
+use crate::cpu::*;
+
+// See https://www.masswerk.at/nowgobang/2021/6502-illegal-opcodes
+// and "No More Secrets - NMOS 6510 Unintended Opcodes"
+
+pub fn no_operation<B: AddressBus>(_cpu: &mut MOS6502<B>) {}
+
+pub fn no_operation_read<B: AddressBus>(_cpu: &mut MOS6502<B>, _value: u8) {}
+
+pub fn jam<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let address = match cpu.instruction_cycle {
+        1 => cpu.reg.pc, // T1
+        2 => 0xFFFF,     // T2
+        _ => 0xFFFE,     // T3, T4
+    };
+    cpu.read(address)?;
+    cpu.tick();
+
+    if cpu.instruction_cycle < 4 {
+        return Ok(Cycle::Next);
+    }
+
+    // The data bus is now stuck at $FF and only a reset will recover the CPU
+    cpu.jammed();
+    Ok(Cycle::Done)
+}
+
+// SLO: ASL then ORA
+pub fn shift_left_or<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = logical_shift_left(cpu, value);
+    logical_inclusive_or(cpu, new_value);
+    new_value
+}
+
+// RLA: ROL then AND
+pub fn rotate_left_and<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = logical_rotate_left(cpu, value);
+    logical_and(cpu, new_value);
+    new_value
+}
+
+// SRE: LSR then EOR
+pub fn shift_right_exclusive_or<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = logical_shift_right(cpu, value);
+    logical_exclusive_or(cpu, new_value);
+    new_value
+}
+
+// RRA: ROR then ADC
+pub fn rotate_right_add<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = logical_rotate_right(cpu, value);
+    add_with_carry(cpu, new_value);
+    new_value
+}
+
+// DCP: DEC then CMP
+pub fn dec_compare<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = value.wrapping_sub(1);
+    compare_ac(cpu, new_value);
+    new_value
+}
+
+// ISC: INC then SBC
+pub fn inc_sub<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = value.wrapping_add(1);
+    sub_with_carry(cpu, new_value);
+    new_value
+}
+
+// LAX: LDA and LDX at the same time
+pub fn load_ac_ix<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    cpu.set_zn(value);
+    cpu.reg.ac = value;
+    cpu.reg.ix = value;
+}
+
+// SAX: stores A & X
+pub fn store_ac_and_ix<B: AddressBus>(cpu: &mut MOS6502<B>) -> u8 {
+    cpu.reg.ac & cpu.reg.ix
+}
+
+// LAS: A, X and SP = M & SP
+pub fn load_ac_ix_sp<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let new_value = value & cpu.reg.sp;
+    cpu.set_zn(new_value);
+    cpu.reg.ac = new_value;
+    cpu.reg.ix = new_value;
+    cpu.reg.sp = new_value;
+}
+
+// SHA: stores A & X & (H + 1)
+pub fn store_ac_and_ix_and_high<B: AddressBus>(cpu: &mut MOS6502<B>, high: u8) -> u8 {
+    cpu.reg.ac & cpu.reg.ix & high
+}
+
+// SHX: stores X & (H + 1)
+pub fn store_ix_and_high<B: AddressBus>(cpu: &mut MOS6502<B>, high: u8) -> u8 {
+    cpu.reg.ix & high
+}
+
+// SHY: stores Y & (H + 1)
+pub fn store_iy_and_high<B: AddressBus>(cpu: &mut MOS6502<B>, high: u8) -> u8 {
+    cpu.reg.iy & high
+}
+
+// TAS: SP = A & X then stores SP & (H + 1)
+pub fn transfer_ac_and_ix_to_sp_and_store<B: AddressBus>(cpu: &mut MOS6502<B>, high: u8) -> u8 {
+    cpu.reg.sp = cpu.reg.ac & cpu.reg.ix;
+    cpu.reg.sp & high
+}
+
+// ANC: AND then copy N into C
+pub fn and_carry<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    logical_and(cpu, value);
+    let negative = cpu.is_set(CPUFLAGS::NEGATIVE);
+    cpu.set(CPUFLAGS::CARRY, negative);
+}
+
+// ALR: AND then LSR A
+pub fn and_shift_right<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let new_value = cpu.reg.ac & value;
+    cpu.reg.ac = logical_shift_right(cpu, new_value);
+}
+
+// ARR: AND then ROR A, with the flags coming out of the adder instead of the shifter
+pub fn and_rotate_right<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let anded = cpu.reg.ac & value;
+    let carry_bit = cpu.is_set(CPUFLAGS::CARRY) as u8;
+    let mut new_value = (anded >> 1) | (carry_bit << 7);
+
+    cpu.set_zn(new_value);
+
+    if !cpu.decimal_enabled() {
+        cpu.set(CPUFLAGS::CARRY, (new_value & (1 << 6)) != 0);
+        cpu.set(
+            CPUFLAGS::OVERFLOW,
+            ((new_value >> 6) ^ (new_value >> 5)) & 1 != 0,
+        );
+        cpu.reg.ac = new_value;
+        return;
+    }
+
+    // In decimal mode the result is BCD fixed up after the rotate
+    cpu.set(CPUFLAGS::OVERFLOW, ((anded ^ new_value) & (1 << 6)) != 0);
+
+    let low = anded & 0x0F;
+    if low + (low & 1) > 5 {
+        new_value = (new_value & 0xF0) | (new_value.wrapping_add(6) & 0x0F);
+    }
+
+    let high = anded >> 4;
+    let carry = high + (high & 1) > 5;
+    if carry {
+        new_value = new_value.wrapping_add(0x60);
+    }
+    cpu.set(CPUFLAGS::CARRY, carry);
+    cpu.reg.ac = new_value;
+}
+
+// SBX: X = (A & X) - M, flags set like CMP
+pub fn and_ix_sub<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let anded = cpu.reg.ac & cpu.reg.ix;
+    let new_value = anded.wrapping_sub(value);
+    cpu.set(CPUFLAGS::CARRY, anded >= value);
+    cpu.set_zn(new_value);
+    cpu.reg.ix = new_value;
+}
+
+// XAA (ANE): A = (A | MAGIC) & X & M
+pub fn transfer_ix_to_ac_and<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let new_value = (cpu.reg.ac | cpu.magic_constant) & cpu.reg.ix & value;
+    cpu.set_zn(new_value);
+    cpu.reg.ac = new_value;
+}
+
+// LXA: A, X = (A | MAGIC) & M
+pub fn load_ac_ix_and<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    let new_value = (cpu.reg.ac | cpu.magic_constant) & value;
+    cpu.set_zn(new_value);
+    cpu.reg.ac = new_value;
+    cpu.reg.ix = new_value;
+}
+
+// The 65C02 $5C NOP reads its absolute operand and then spends another 5 cycles on the bus
+pub fn no_operation_long<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.data = cpu.read(cpu.reg.pc)?;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.read(cpu.reg.pc)?;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T3
+        3 => {
+            cpu.read(0xFF00 | cpu.data as u16)?;
+        }
+        // T4 - T7
+        _ => {
+            cpu.read(0xFFFF)?;
+        }
+    }
+    cpu.tick();
+
+    if cpu.instruction_cycle < 7 {
+        return Ok(Cycle::Next);
+    }
+    Ok(Cycle::Done)
+}
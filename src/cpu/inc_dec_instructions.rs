@@ -1,37 +1,37 @@
 use crate::cpu::*;
 
-pub fn inc_memory(cpu: &mut MOS6502, value: u8) -> u8 {
+pub fn inc_memory<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
     let new_value = (value as u16 + 1) as u8;
     cpu.set_zn(new_value);
     new_value
 }
 
-pub fn inc_ix(cpu: &mut MOS6502) {
+pub fn inc_ix<B: AddressBus>(cpu: &mut MOS6502<B>) {
     let new_ix = (cpu.reg.ix as u16 + 1) as u8;
     cpu.set_zn(new_ix);
     cpu.reg.ix = new_ix
 }
 
-pub fn inc_iy(cpu: &mut MOS6502) {
+pub fn inc_iy<B: AddressBus>(cpu: &mut MOS6502<B>) {
     let new_iy = (cpu.reg.iy as u16 + 1) as u8;
     cpu.set_zn(new_iy);
     cpu.reg.iy = new_iy
 }
 
-pub fn dec_memory(cpu: &mut MOS6502, value: u8) -> u8 {
-    let new_value = unsafe { value.unchecked_sub(1) };
+pub fn dec_memory<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    let new_value = value.wrapping_sub(1);
     cpu.set_zn(new_value);
     new_value
 }
 
-pub fn dec_ix(cpu: &mut MOS6502) {
-    let new_ix = unsafe { cpu.reg.ix.unchecked_sub(1) };
+pub fn dec_ix<B: AddressBus>(cpu: &mut MOS6502<B>) {
+    let new_ix = cpu.reg.ix.wrapping_sub(1);
     cpu.set_zn(new_ix);
     cpu.reg.ix = new_ix
 }
 
-pub fn dec_iy(cpu: &mut MOS6502) {
-    let new_iy = unsafe { cpu.reg.iy.unchecked_sub(1) };
+pub fn dec_iy<B: AddressBus>(cpu: &mut MOS6502<B>) {
+    let new_iy = cpu.reg.iy.wrapping_sub(1);
     cpu.set_zn(new_iy);
     cpu.reg.iy = new_iy
 }
@@ -1,44 +1,44 @@
 use crate::cpu::*;
 
-pub fn logical_and(cpu: &mut MOS6502, value: u8) {
+pub fn logical_and<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     let new_accumulator = cpu.reg.ac & value;
     cpu.set_zn(new_accumulator);
     cpu.reg.ac = new_accumulator;
 }
 
-pub fn logical_exclusive_or(cpu: &mut MOS6502, value: u8) {
+pub fn logical_exclusive_or<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     let new_accumulator = cpu.reg.ac ^ value;
     cpu.set_zn(new_accumulator);
     cpu.reg.ac = new_accumulator;
 }
 
-pub fn logical_inclusive_or(cpu: &mut MOS6502, value: u8) {
+pub fn logical_inclusive_or<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     let new_accumulator = cpu.reg.ac | value;
     cpu.set_zn(new_accumulator);
     cpu.reg.ac = new_accumulator;
 }
 
-pub fn logical_bit_test(cpu: &mut MOS6502, value: u8) {
+pub fn logical_bit_test<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     cpu.set(CPUFLAGS::ZERO, (value & cpu.reg.ac) == 0);
     cpu.set(CPUFLAGS::NEGATIVE, (value & (1 << 7)) != 0);
     cpu.set(CPUFLAGS::OVERFLOW, (value & (1 << 6)) != 0);
 }
 
-pub fn logical_shift_left(cpu: &mut MOS6502, value: u8) -> u8 {
+pub fn logical_shift_left<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
     let new_value = value << 1;
     cpu.set(CPUFLAGS::CARRY, (value & (1 << 7)) != 0); // 7th bit for carry
     cpu.set_zn(new_value);
     new_value
 }
 
-pub fn logical_shift_right(cpu: &mut MOS6502, value: u8) -> u8 {
+pub fn logical_shift_right<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
     let new_value = value >> 1;
     cpu.set(CPUFLAGS::CARRY, (value & 1) != 0); // 0th bit for carry
     cpu.set_zn(new_value);
     new_value
 }
 
-pub fn logical_rotate_left(cpu: &mut MOS6502, value: u8) -> u8 {
+pub fn logical_rotate_left<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
     let carry_bit = match cpu.is_set(CPUFLAGS::CARRY) {
         true => 1_u8,
         false => 0_u8,
@@ -49,7 +49,7 @@ pub fn logical_rotate_left(cpu: &mut MOS6502, value: u8) -> u8 {
     new_value
 }
 
-pub fn logical_rotate_right(cpu: &mut MOS6502, value: u8) -> u8 {
+pub fn logical_rotate_right<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
     let carry_bit = match cpu.is_set(CPUFLAGS::CARRY) {
         true => 1_u8,
         false => 0_u8,
@@ -60,3 +60,27 @@ pub fn logical_rotate_right(cpu: &mut MOS6502, value: u8) -> u8 {
     cpu.set_zn(new_value);
     new_value
 }
+
+// The 65C02 BIT #imm only affects the Z flag
+pub fn logical_bit_test_immediate<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
+    cpu.set(CPUFLAGS::ZERO, (value & cpu.reg.ac) == 0);
+}
+
+pub fn test_and_set_bits<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    cpu.set(CPUFLAGS::ZERO, (value & cpu.reg.ac) == 0);
+    value | cpu.reg.ac
+}
+
+pub fn test_and_reset_bits<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    cpu.set(CPUFLAGS::ZERO, (value & cpu.reg.ac) == 0);
+    value & !cpu.reg.ac
+}
+
+// RMB and SMB (Rockwell and WDC 65C02)
+pub fn reset_memory_bit<const BIT: u8, B: AddressBus>(_cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    value & !(1 << BIT)
+}
+
+pub fn set_memory_bit<const BIT: u8, B: AddressBus>(_cpu: &mut MOS6502<B>, value: u8) -> u8 {
+    value | (1 << BIT)
+}
@@ -5,9 +5,12 @@ mod indirectx;
 mod indirecty;
 mod readwrite;
 mod zeropage;
+mod zeropageindirect;
 mod zeropagex;
 mod zeropagey;
 
+use crate::cpu::arithmetic_instructions::decimal_fixup_cycle;
+use crate::cpu::branching_instructions::branch;
 use crate::cpu::*;
 use absolute::*;
 use absolutex::*;
@@ -16,13 +19,20 @@ use indirectx::*;
 use indirecty::*;
 use readwrite::*;
 use zeropage::*;
+use zeropageindirect::*;
 use zeropagex::*;
 use zeropagey::*;
 
-type Inst = dyn Fn(&mut MOS6502);
-type ReadInst = dyn Fn(&mut MOS6502, u8);
-type WriteInst = dyn Fn(&mut MOS6502) -> u8;
-type ReadWriteInst = dyn Fn(&mut MOS6502, u8) -> u8;
+pub type Inst<B> = fn(&mut MOS6502<B>);
+pub type ReadInst<B> = fn(&mut MOS6502<B>, u8);
+pub type WriteInst<B> = fn(&mut MOS6502<B>) -> u8;
+pub type ReadWriteInst<B> = fn(&mut MOS6502<B>, u8) -> u8;
+// Receives the high byte of the base address plus one and returns the value to store
+pub type UnstableWriteInst<B> = fn(&mut MOS6502<B>, u8) -> u8;
+// Whether the branch is taken
+pub type BranchInst<B> = fn(&mut MOS6502<B>) -> bool;
+// Runs the cycle given by cpu.instruction_cycle
+pub type CycleInst<B> = fn(&mut MOS6502<B>) -> CycleResult;
 
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum AddressingMode {
@@ -39,73 +49,68 @@ pub enum AddressingMode {
     AbsoluteY,
     IndirectX,
     IndirectY,
+    ZeroPageIndirect,        // 65C02 only
+    AbsoluteIndexedIndirect, // 65C02 only
+    ZeroPageRelative,        // Rockwell and WDC 65C02 only
 }
 
-// Matches all 151 opcodes to their correct addressing_mode
-pub fn get_addressing_mode(opcode: u8) -> AddressingMode {
-    if opcode == 0x6C {
-        return AddressingMode::Indirect;
-    }
-    if opcode == 0x20 {
-        return AddressingMode::Absolute;
-    }
-    if opcode == 0xC0 || opcode == 0xA2 || opcode == 0xA0 || opcode == 0xE0 {
-        return AddressingMode::Immediate;
-    }
-    if opcode == 0x4A || opcode == 0xA || opcode == 0x2A || opcode == 0x6A {
-        return AddressingMode::Accumulator;
-    }
-    if opcode == 0xB6 || opcode == 0x96 {
-        return AddressingMode::ZeroPageY;
+// Whether the opcode is part of the official instruction set of the variant
+#[rustfmt::skip]
+pub fn is_documented(opcode: u8, variant: CpuVariant) -> bool {
+    if !variant.is_cmos() {
+        return matches!(
+            opcode,
+            0x00 | 0x01 | 0x05 | 0x06 | 0x08 | 0x09 | 0x0A | 0x0D | 0x0E |
+            0x10 | 0x11 | 0x15 | 0x16 | 0x18 | 0x19 | 0x1D | 0x1E |
+            0x20 | 0x21 | 0x24 | 0x25 | 0x26 | 0x28 | 0x29 | 0x2A | 0x2C | 0x2D | 0x2E |
+            0x30 | 0x31 | 0x35 | 0x36 | 0x38 | 0x39 | 0x3D | 0x3E |
+            0x40 | 0x41 | 0x45 | 0x46 | 0x48 | 0x49 | 0x4A | 0x4C | 0x4D | 0x4E |
+            0x50 | 0x51 | 0x55 | 0x56 | 0x58 | 0x59 | 0x5D | 0x5E |
+            0x60 | 0x61 | 0x65 | 0x66 | 0x68 | 0x69 | 0x6A | 0x6C | 0x6D | 0x6E |
+            0x70 | 0x71 | 0x75 | 0x76 | 0x78 | 0x79 | 0x7D | 0x7E |
+            0x81 | 0x84 | 0x85 | 0x86 | 0x88 | 0x8A | 0x8C | 0x8D | 0x8E |
+            0x90 | 0x91 | 0x94 | 0x95 | 0x96 | 0x98 | 0x99 | 0x9A | 0x9D |
+            0xA0 | 0xA1 | 0xA2 | 0xA4 | 0xA5 | 0xA6 | 0xA8 | 0xA9 | 0xAA | 0xAC | 0xAD | 0xAE |
+            0xB0 | 0xB1 | 0xB4 | 0xB5 | 0xB6 | 0xB8 | 0xB9 | 0xBA | 0xBC | 0xBD | 0xBE |
+            0xC0 | 0xC1 | 0xC4 | 0xC5 | 0xC6 | 0xC8 | 0xC9 | 0xCA | 0xCC | 0xCD | 0xCE |
+            0xD0 | 0xD1 | 0xD5 | 0xD6 | 0xD8 | 0xD9 | 0xDD | 0xDE |
+            0xE0 | 0xE1 | 0xE4 | 0xE5 | 0xE6 | 0xE8 | 0xE9 | 0xEA | 0xEC | 0xED | 0xEE |
+            0xF0 | 0xF1 | 0xF5 | 0xF6 | 0xF8 | 0xF9 | 0xFD | 0xFE
+        );
     }
 
-    // Lots of magic numbers (Might be a better way of doing this but whatever its shorter then 151 lines)
-    if (opcode & 0b11111) == 0b10000 {
-        return AddressingMode::Relative;
-    }
-    if (opcode & 0b11111) == 0b10001 {
-        return AddressingMode::IndirectY;
-    }
-    if (opcode & 0b11111) == 0b00001 {
-        return AddressingMode::IndirectX;
-    }
-    if (opcode & 0b101) == 0 {
-        return AddressingMode::Implied;
-    }
-    if (opcode & 0b1111) == 0b1010 {
-        return AddressingMode::Accumulator;
-    }
-    if (opcode & 0b11100) == 0b01100 {
-        return AddressingMode::Absolute;
-    }
-    if (opcode & 0b10100) == 0 {
-        return AddressingMode::Immediate;
-    }
-    if (opcode & 0b11100) == 0b00100 {
-        return AddressingMode::ZeroPage;
-    }
-    if (opcode & 0b11100) == 0b10100 {
-        return AddressingMode::ZeroPageX;
-    }
-    if (opcode & 0b11100) == 0b11000 || opcode == 0b10111110 {
-        return AddressingMode::AbsoluteY;
-    }
-    if (opcode & 0b11100) == 0b11100 {
-        return AddressingMode::AbsoluteX;
+    match opcode {
+        // Reserved NOPs
+        0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 |
+        0x44 | 0x54 | 0xD4 | 0xF4 | 0x5C | 0xDC | 0xFC => false,
+        0xCB | 0xDB => variant.has_wait_and_stop(),
+        _ if (opcode & 0b111) == 0b111 => variant.has_bit_instructions(),
+        _ => (opcode & 0b11) != 0b11,
     }
-
-    AddressingMode::Implied
 }
 
-pub fn instruction_implied(cpu: &mut MOS6502, func: &Inst) {
-    implied_1read(cpu);
+#[inline(always)]
+pub fn instruction_implied<B: AddressBus>(cpu: &mut MOS6502<B>, func: Inst<B>) -> CycleResult {
+    // T1
+    cpu.read(cpu.reg.pc)?;
+    cpu.tick();
     func(cpu);
+    Ok(Cycle::Done)
 }
 
-pub fn instruction_read(cpu: &mut MOS6502, addressing_mode: AddressingMode, func: &ReadInst) {
-    match addressing_mode {
+#[inline(always)]
+pub fn instruction_read<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    addressing_mode: AddressingMode,
+    func: ReadInst<B>,
+) -> CycleResult {
+    // ADC and SBC set this on the 65C02 when they need another cycle in decimal mode
+    if cpu.decimal_fixup {
+        return decimal_fixup_cycle(cpu);
+    }
+
+    let cycle = match addressing_mode {
         AddressingMode::Immediate => immediate_1read(cpu, func),
-        AddressingMode::Relative => immediate_1read(cpu, func),
         AddressingMode::Absolute => absolute_3read(cpu, func),
         AddressingMode::ZeroPage => zeropage_2read(cpu, func),
         AddressingMode::ZeroPageX => zeropagex_3read(cpu, func),
@@ -114,11 +119,22 @@ pub fn instruction_read(cpu: &mut MOS6502, addressing_mode: AddressingMode, func
         AddressingMode::AbsoluteY => absolutey_3read(cpu, func),
         AddressingMode::IndirectX => indirectx_5read(cpu, func),
         AddressingMode::IndirectY => indirecty_5read(cpu, func),
-        _ => panic!("Invalid instruction read addressing mode!"),
+        AddressingMode::ZeroPageIndirect => zeropageindirect_4read(cpu, func),
+        _ => cpu.unsupported("read addressing mode"),
+    }?;
+
+    if cpu.decimal_fixup {
+        return Ok(Cycle::Next);
     }
+    Ok(cycle)
 }
 
-pub fn instruction_write(cpu: &mut MOS6502, addressing_mode: AddressingMode, func: &WriteInst) {
+#[inline(always)]
+pub fn instruction_write<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    addressing_mode: AddressingMode,
+    func: WriteInst<B>,
+) -> CycleResult {
     match addressing_mode {
         AddressingMode::Absolute => absolute_3write(cpu, func),
         AddressingMode::ZeroPage => zeropage_2write(cpu, func),
@@ -128,34 +144,173 @@ pub fn instruction_write(cpu: &mut MOS6502, addressing_mode: AddressingMode, fun
         AddressingMode::AbsoluteY => absolutey_4write(cpu, func),
         AddressingMode::IndirectX => indirectx_5write(cpu, func),
         AddressingMode::IndirectY => indirecty_5write(cpu, func),
-        _ => panic!("Invalid instruction write addressing mode!"),
+        AddressingMode::ZeroPageIndirect => zeropageindirect_4write(cpu, func),
+        _ => cpu.unsupported("write addressing mode"),
     }
 }
 
-pub fn instruction_read_move_write(
-    cpu: &mut MOS6502,
+#[inline(always)]
+pub fn instruction_read_move_write<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
     addressing_mode: AddressingMode,
-    func: &ReadWriteInst,
-) {
+    func: ReadWriteInst<B>,
+) -> CycleResult {
     match addressing_mode {
         AddressingMode::Accumulator => ac1_rmw(cpu, func),
         AddressingMode::ZeroPage => zeropage_4rmw(cpu, func),
         AddressingMode::ZeroPageX => zeropagex_5rmw(cpu, func),
         AddressingMode::Absolute => absolute_5rmw(cpu, func),
         AddressingMode::AbsoluteX => absolutex_6rmw(cpu, func),
-        _ => panic!("Invalid instruction read move write addressing mode!"),
+        AddressingMode::AbsoluteY => absolutey_6rmw(cpu, func),
+        AddressingMode::IndirectX => indirectx_7rmw(cpu, func),
+        AddressingMode::IndirectY => indirecty_7rmw(cpu, func),
+        _ => cpu.unsupported("read-modify-write addressing mode"),
+    }
+}
+
+// The 65C02 shifts and rotates, which skip the abs,X fixup cycle without a page crossing
+#[inline(always)]
+pub fn instruction_indexed_shift<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    addressing_mode: AddressingMode,
+    func: ReadWriteInst<B>,
+) -> CycleResult {
+    match addressing_mode {
+        AddressingMode::AbsoluteX => absolutex_5rmw(cpu, func),
+        _ => cpu.unsupported("indexed shift addressing mode"),
     }
 }
 
-fn implied_1read(cpu: &mut MOS6502) {
-    cpu.read(cpu.reg.pc);
+// Used by the undocumented SHA, SHX, SHY and TAS instructions which AND the stored value
+// with the high byte of the base address + 1
+pub fn instruction_write_unstable<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    addressing_mode: AddressingMode,
+    func: UnstableWriteInst<B>,
+) -> CycleResult {
+    match addressing_mode {
+        AddressingMode::AbsoluteX => absolutex_4write_unstable(cpu, func),
+        AddressingMode::AbsoluteY => absolutey_4write_unstable(cpu, func),
+        AddressingMode::IndirectY => indirecty_5write_unstable(cpu, func),
+        _ => cpu.unsupported("unstable write addressing mode"),
+    }
+}
+
+#[inline(always)]
+pub fn instruction_branch<B: AddressBus>(cpu: &mut MOS6502<B>, func: BranchInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        1 => {
+            // T1
+            cpu.data = cpu.read(cpu.reg.pc)?;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+            cpu.tick();
+
+            // A taken branch adds its own cycles after the offset fetch
+            if func(cpu) {
+                Ok(Cycle::Next)
+            } else {
+                Ok(Cycle::Done)
+            }
+        }
+        cycle => branch(cpu, cycle - 1),
+    }
+}
+
+// The dummy read of the byte after the opcode which starts every implied instruction
+pub fn implied_read<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    cpu.read(cpu.reg.pc)?;
+    cpu.tick();
+    Ok(Cycle::Next)
+}
+
+// When the indexing crosses a page the high byte of the target address gets replaced
+// by the value being stored
+fn unstable_write_address(address: u16, indexed_address: u16, value: u8) -> u16 {
+    if same_page(address, indexed_address) {
+        indexed_address
+    } else {
+        ((value as u16) << 8) | (indexed_address & 0x00FF)
+    }
+}
+
+// The last cycles of the addressing modes, once cpu.address holds the effective address
+
+fn read_cycle<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    let value = cpu.read(cpu.address)?;
+    func(cpu, value); // Perform the operation
+    cpu.tick();
+    Ok(Cycle::Done)
+}
+
+fn write_cycle<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    let value = func(cpu);
+    cpu.write(cpu.address, value);
+    cpu.tick();
+    Ok(Cycle::Done)
+}
+
+fn write_unstable_cycle<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: UnstableWriteInst<B>,
+) -> CycleResult {
+    let value = func(cpu, ((cpu.base_address >> 8) as u8).wrapping_add(1));
+    let address = unstable_write_address(cpu.base_address, cpu.address, value);
+    cpu.write(address, value);
+    cpu.tick();
+    Ok(Cycle::Done)
+}
+
+// The cycle spent fixing the high byte of an indexed address. The NMOS 6502 reads from the
+// base page with the low byte already indexed, the 65C02 reads the last operand byte again
+// when the page was crossed
+fn fixup_read_cycle<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let address = if cpu.is_cmos() && !same_page(cpu.base_address, cpu.address) {
+        cpu.reg.pc.wrapping_sub(1)
+    } else {
+        (cpu.base_address & 0xFF00) | (cpu.address & 0x00FF)
+    };
+    cpu.read(address)?;
     cpu.tick();
+    Ok(Cycle::Next)
+}
+
+// Reads the value, writes it back unchanged while the operation runs then writes the result.
+// The 65C02 reads it a second time instead of writing it back
+fn read_modify_write_cycle<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: ReadWriteInst<B>,
+    cycle: u8,
+) -> CycleResult {
+    match cycle {
+        1 => {
+            cpu.data = cpu.read(cpu.address)?;
+            cpu.tick();
+            Ok(Cycle::Next)
+        }
+        2 if cpu.is_cmos() => {
+            cpu.read(cpu.address)?;
+            cpu.tick();
+            Ok(Cycle::Next)
+        }
+        2 => {
+            cpu.write(cpu.address, cpu.data);
+            cpu.tick();
+            Ok(Cycle::Next)
+        }
+        _ => {
+            let value = func(cpu, cpu.data);
+            cpu.write(cpu.address, value);
+            cpu.tick();
+            Ok(Cycle::Done)
+        }
+    }
 }
 
-fn immediate_1read(cpu: &mut MOS6502, func: &ReadInst) {
+fn immediate_1read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
     // T1
-    let value = cpu.read(cpu.reg.pc);
-    cpu.reg.pc += 1;
+    let value = cpu.read(cpu.reg.pc)?;
+    cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
     func(cpu, value);
     cpu.tick();
+    Ok(Cycle::Done)
 }
@@ -5,54 +5,42 @@ use crate::cpu::MOS6502;
 // Absolute Addressing //
 /////////////////////////
 
-fn fetch_absolute_2(cpu: &mut MOS6502) -> u16 {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
+fn fetch_absolute_2<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.address = cpu.read(cpu.reg.pc)? as u16;
+    } else {
+        // T2
+        cpu.address |= (cpu.read(cpu.reg.pc)? as u16) << 8;
+    }
+    cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
     cpu.tick();
-
-    // T2
-    let address = address | (cpu.read(cpu.reg.pc) as u16) << 8;
-    cpu.reg.pc += 1;
-    cpu.tick();
-
-    address
+    Ok(Cycle::Next)
 }
 
-pub fn absolute_3read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2
-    let address = fetch_absolute_2(cpu);
-
-    // T3
-    let value = cpu.read(address);
-    func(cpu, value);
-    cpu.tick();
+pub fn absolute_3read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolute_2(cpu),
+        // T3
+        _ => read_cycle(cpu, func),
+    }
 }
 
-pub fn absolute_3write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2
-    let address = fetch_absolute_2(cpu);
-
-    // T3
-    let value = func(cpu);
-    cpu.write(address, value);
-    cpu.tick();
+pub fn absolute_3write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolute_2(cpu),
+        // T3
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn absolute_5rmw(cpu: &mut MOS6502, func: &ReadWriteInst) {
-    // T1, T2
-    let address = fetch_absolute_2(cpu);
-
-    // T3
-    let value = cpu.read(address);
-    cpu.tick();
-
-    // T4
-    cpu.write(address, value);
-    cpu.tick();
-
-    // T5
-    let value = func(cpu, value);
-    cpu.write(address, value);
-    cpu.tick();
+pub fn absolute_5rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolute_2(cpu),
+        // T3, T4, T5
+        cycle => read_modify_write_cycle(cpu, func, cycle - 2),
+    }
 }
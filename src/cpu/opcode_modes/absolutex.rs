@@ -5,68 +5,76 @@ use crate::cpu::MOS6502;
 // Absolute,X Addressing //
 ///////////////////////////
 
-fn fetch_absolutex_2(cpu: &mut MOS6502) -> (u16, u16) {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
-    cpu.tick();
-
-    // T2
-    let address = address | (cpu.read(cpu.reg.pc) as u16) << 8;
-    let address_x = address + cpu.reg.ix as u16;
-    cpu.reg.pc += 1;
+fn fetch_absolutex_2<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.base_address = cpu.read(cpu.reg.pc)? as u16;
+    } else {
+        // T2
+        cpu.base_address |= (cpu.read(cpu.reg.pc)? as u16) << 8;
+        cpu.address = cpu.base_address.wrapping_add(cpu.reg.ix as u16);
+    }
+    cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
     cpu.tick();
-
-    (address, address_x)
+    Ok(Cycle::Next)
 }
 
-pub fn absolutex_3read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2
-    let (address, address_x) = fetch_absolutex_2(cpu);
-
-    // T3
-    if !same_page(address, address_x) {
-        cpu.read(address_x - 0x100); // Perform buggy read in previous page
-        cpu.tick();
+pub fn absolutex_3read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutex_2(cpu),
+        // T3, fix the high byte on a page crossing
+        3 if !same_page(cpu.base_address, cpu.address) => fixup_read_cycle(cpu),
+        // T3 or T4
+        _ => read_cycle(cpu, func),
     }
-
-    // T3 or T4
-    let value = cpu.read(address_x);
-    func(cpu, value);
-    cpu.tick();
 }
 
-pub fn absolutex_4write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2
-    let (address, address_x) = fetch_absolutex_2(cpu);
-
-    // T3
-    cpu.read((address & 0xFF00) | (address_x & 0x00FF));
-    cpu.tick();
-
-    // T4
-    let value = func(cpu);
-    cpu.write(address_x, value);
-    cpu.tick();
+pub fn absolutex_4write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutex_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn absolutex_6rmw(cpu: &mut MOS6502, func: &ReadWriteInst) {
-    let (_, address_x) = fetch_absolutex_2(cpu);
-
-    // T3
-    cpu.read(address_x);
-    cpu.tick();
-
-    // T4
-    let value = cpu.read(address_x);
-    cpu.tick();
+pub fn absolutex_6rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutex_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4, T5, T6
+        cycle => read_modify_write_cycle(cpu, func, cycle - 3),
+    }
+}
 
-    // T5
-    cpu.write(address_x, value);
-    cpu.tick();
+// The 65C02 shifts and rotates only spend the fixup cycle on a page crossing
+pub fn absolutex_5rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    let fixup = !same_page(cpu.base_address, cpu.address);
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutex_2(cpu),
+        // T3
+        3 if fixup => fixup_read_cycle(cpu),
+        // T4, T5, T6 or T3, T4, T5
+        cycle => read_modify_write_cycle(cpu, func, cycle - 2 - fixup as u8),
+    }
+}
 
-    // T6
-    let value = func(cpu, value);
-    cpu.write(address_x, value);
-    cpu.tick();
+pub fn absolutex_4write_unstable<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: UnstableWriteInst<B>,
+) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutex_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4
+        _ => write_unstable_cycle(cpu, func),
+    }
 }
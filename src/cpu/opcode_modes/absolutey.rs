@@ -5,43 +5,63 @@ use crate::cpu::MOS6502;
 // Absolute,Y Addressing //
 ///////////////////////////
 
-fn fetch_absolutey_2(cpu: &mut MOS6502) -> (u16, u16) {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
+fn fetch_absolutey_2<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.base_address = cpu.read(cpu.reg.pc)? as u16;
+    } else {
+        // T2
+        cpu.base_address |= (cpu.read(cpu.reg.pc)? as u16) << 8;
+        cpu.address = cpu.base_address.wrapping_add(cpu.reg.iy as u16);
+    }
+    cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
     cpu.tick();
+    Ok(Cycle::Next)
+}
 
-    // T2
-    let address = address | (cpu.read(cpu.reg.pc) as u16) << 8;
-    let address_y = address + cpu.reg.iy as u16;
-    cpu.reg.pc += 1;
-    cpu.tick();
+pub fn absolutey_3read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutey_2(cpu),
+        // T3, fix the high byte on a page crossing
+        3 if !same_page(cpu.base_address, cpu.address) => fixup_read_cycle(cpu),
+        // T3 or T4
+        _ => read_cycle(cpu, func),
+    }
+}
 
-    (address, address_y)
+pub fn absolutey_4write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutey_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn absolutey_3read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2
-    let (address, address_y) = fetch_absolutey_2(cpu);
-    // T3
-    if !same_page(address, address_y) {
-        cpu.read(address_y - 0x100); // Perform buggy read in previous page
-        cpu.tick();
+pub fn absolutey_4write_unstable<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: UnstableWriteInst<B>,
+) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutey_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4
+        _ => write_unstable_cycle(cpu, func),
     }
-    // T3 or T4
-    let value = cpu.read(address_y);
-    func(cpu, value);
-    cpu.tick();
 }
 
-pub fn absolutey_4write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2
-    let (address, address_y) = fetch_absolutey_2(cpu);
-    // T3
-    cpu.read((address & 0xFF00) | (address_y & 0x00FF));
-    cpu.tick();
-    // T4
-    let value = func(cpu);
-    cpu.write(address_y, value);
-    cpu.tick();
+pub fn absolutey_6rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_absolutey_2(cpu),
+        // T3
+        3 => fixup_read_cycle(cpu),
+        // T4, T5, T6
+        cycle => read_modify_write_cycle(cpu, func, cycle - 3),
+    }
 }
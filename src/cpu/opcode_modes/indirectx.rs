@@ -5,42 +5,55 @@ use crate::cpu::MOS6502;
 // Indirect,X Addressing //
 ///////////////////////////
 
-fn fetch_indirectx(cpu: &mut MOS6502) -> u16 {
-    // T1
-    let indirect_address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
+fn fetch_indirectx<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.pointer = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.read(cpu.pointer)?;
+        }
+        // T3
+        3 => {
+            let zp_address = (cpu.pointer + cpu.reg.ix as u16) & 0xFF;
+            cpu.address = cpu.read(zp_address)? as u16;
+        }
+        // T4
+        _ => {
+            let zp_address = (cpu.pointer + cpu.reg.ix as u16 + 1) & 0xFF;
+            cpu.address |= (cpu.read(zp_address)? as u16) << 8;
+        }
+    }
     cpu.tick();
-    // T2
-    cpu.read(indirect_address);
-    cpu.tick();
-    // T3
-    let zp_address = (indirect_address + cpu.reg.ix as u16) & 0xFF;
-    let address = cpu.read(zp_address) as u16;
-    cpu.tick();
-    // T4
-    let zp_address = (indirect_address + cpu.reg.ix as u16 + 1) & 0xFF;
-    let address = address | ((cpu.read(zp_address) as u16) << 8);
-    cpu.tick();
-
-    address
+    Ok(Cycle::Next)
 }
 
-pub fn indirectx_5read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2, T3, T4
-    let address = fetch_indirectx(cpu);
-
-    // T5
-    let value = cpu.read(address);
-    func(cpu, value);
-    cpu.tick();
+pub fn indirectx_5read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3, T4
+        1..=4 => fetch_indirectx(cpu),
+        // T5
+        _ => read_cycle(cpu, func),
+    }
 }
 
-pub fn indirectx_5write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2, T3, T4
-    let address = fetch_indirectx(cpu);
+pub fn indirectx_5write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3, T4
+        1..=4 => fetch_indirectx(cpu),
+        // T5
+        _ => write_cycle(cpu, func),
+    }
+}
 
-    // T5
-    let value = func(cpu);
-    cpu.write(address, value);
-    cpu.tick();
+pub fn indirectx_7rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3, T4
+        1..=4 => fetch_indirectx(cpu),
+        // T5, T6, T7
+        cycle => read_modify_write_cycle(cpu, func, cycle - 4),
+    }
 }
@@ -5,48 +5,70 @@ use crate::cpu::MOS6502;
 // Indirect,Y Addressing //
 ///////////////////////////
 
-fn fetch_indirecty_3(cpu: &mut MOS6502) -> (u16, u16) {
-    // T1
-    let zp_address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
-    cpu.tick();
-    // T2
-    let address = cpu.read(zp_address) as u16;
-    cpu.tick();
-    // T3
-    let address = address | ((cpu.read((zp_address + 1) & 0xFF) as u16) << 8);
-    let address_y = address + cpu.reg.iy as u16;
+fn fetch_indirecty_3<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.pointer = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.base_address = cpu.read(cpu.pointer)? as u16;
+        }
+        // T3
+        _ => {
+            cpu.base_address |= (cpu.read((cpu.pointer + 1) & 0xFF)? as u16) << 8;
+            cpu.address = cpu.base_address.wrapping_add(cpu.reg.iy as u16);
+        }
+    }
     cpu.tick();
-
-    (address, address_y)
+    Ok(Cycle::Next)
 }
 
-pub fn indirecty_5read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2, T3
-    let (address, address_y) = fetch_indirecty_3(cpu);
-
-    // T4
-    if !same_page(address, address_y) {
-        cpu.read((address & 0xFF00) | (address_y | 0x00FF));
-        cpu.tick();
+pub fn indirecty_5read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_indirecty_3(cpu),
+        // T4
+        4 if !same_page(cpu.base_address, cpu.address) => fixup_read_cycle(cpu),
+        // T4 or T5
+        _ => read_cycle(cpu, func),
     }
-
-    // T4 or T5
-    let value = cpu.read(address_y);
-    func(cpu, value);
-    cpu.tick();
 }
 
-pub fn indirecty_5write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2, T3
-    let (address, address_y) = fetch_indirecty_3(cpu);
+pub fn indirecty_5write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_indirecty_3(cpu),
+        // T4
+        4 => fixup_read_cycle(cpu),
+        // T5
+        _ => write_cycle(cpu, func),
+    }
+}
 
-    // T4
-    cpu.read((address & 0xFF00) | (address_y | 0x00FF));
-    cpu.tick();
+pub fn indirecty_5write_unstable<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: UnstableWriteInst<B>,
+) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_indirecty_3(cpu),
+        // T4
+        4 => fixup_read_cycle(cpu),
+        // T5
+        _ => write_unstable_cycle(cpu, func),
+    }
+}
 
-    // T5
-    let value = func(cpu);
-    cpu.write(address_y, value);
-    cpu.tick();
+pub fn indirecty_7rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_indirecty_3(cpu),
+        // T4
+        4 => fixup_read_cycle(cpu),
+        // T5, T6, T7
+        cycle => read_modify_write_cycle(cpu, func, cycle - 4),
+    }
 }
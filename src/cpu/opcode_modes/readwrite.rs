@@ -5,9 +5,10 @@ use crate::cpu::MOS6502;
 // Read,Write Addressing //
 ///////////////////////////
 
-pub fn ac1_rmw(cpu: &mut MOS6502, func: &ReadWriteInst) {
+pub fn ac1_rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
     // T1
-    cpu.read(cpu.reg.pc);
+    cpu.read(cpu.reg.pc)?;
     cpu.reg.ac = func(cpu, cpu.reg.ac);
     cpu.tick();
+    Ok(Cycle::Done)
 }
@@ -5,49 +5,37 @@ use crate::cpu::MOS6502;
 // ZeroPage Addressing //
 /////////////////////////
 
-fn fetch_zeropage1(cpu: &mut MOS6502) -> u16 {
+fn fetch_zeropage1<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
     // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    cpu.reg.pc += 1;
+    cpu.address = cpu.read(cpu.reg.pc)? as u16;
+    cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
     cpu.tick();
-
-    address
+    Ok(Cycle::Next)
 }
 
-pub fn zeropage_2read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1
-    let address = fetch_zeropage1(cpu);
-
-    // T2
-    let value = cpu.read(address);
-    func(cpu, value); // Perform the operation
-    cpu.tick();
+pub fn zeropage_2read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => fetch_zeropage1(cpu),
+        // T2
+        _ => read_cycle(cpu, func),
+    }
 }
 
-pub fn zeropage_2write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1
-    let address = fetch_zeropage1(cpu);
-
-    // T2
-    let value = func(cpu);
-    cpu.write(address, value);
-    cpu.tick();
+pub fn zeropage_2write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => fetch_zeropage1(cpu),
+        // T2
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn zeropage_4rmw(cpu: &mut MOS6502, func: &ReadWriteInst) {
-    // T1
-    let address = fetch_zeropage1(cpu);
-
-    // T2
-    let value = cpu.read(address);
-    cpu.tick();
-
-    // T3
-    cpu.write(address, value);
-    cpu.tick();
-
-    // T4
-    let value = func(cpu, value);
-    cpu.write(address, value);
-    cpu.tick();
+pub fn zeropage_4rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => fetch_zeropage1(cpu),
+        // T2, T3, T4
+        cycle => read_modify_write_cycle(cpu, func, cycle - 1),
+    }
 }
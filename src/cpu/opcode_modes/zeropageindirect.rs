@@ -0,0 +1,50 @@
+use crate::cpu::opcode_modes::*;
+use crate::cpu::MOS6502;
+
+///////////////////////////////////
+// (ZeroPage) Addressing (65C02) //
+///////////////////////////////////
+
+fn fetch_zeropageindirect_3<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            cpu.pointer = cpu.read(cpu.reg.pc)? as u16;
+            cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+        }
+        // T2
+        2 => {
+            cpu.address = cpu.read(cpu.pointer)? as u16;
+        }
+        // T3
+        _ => {
+            cpu.address |= (cpu.read((cpu.pointer + 1) & 0xFF)? as u16) << 8;
+        }
+    }
+    cpu.tick();
+    Ok(Cycle::Next)
+}
+
+pub fn zeropageindirect_4read<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: ReadInst<B>,
+) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_zeropageindirect_3(cpu),
+        // T4
+        _ => read_cycle(cpu, func),
+    }
+}
+
+pub fn zeropageindirect_4write<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    func: WriteInst<B>,
+) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2, T3
+        1..=3 => fetch_zeropageindirect_3(cpu),
+        // T4
+        _ => write_cycle(cpu, func),
+    }
+}
@@ -5,48 +5,43 @@ use crate::cpu::MOS6502;
 // ZeroPage,X Addressing //
 ///////////////////////////
 
-fn fetch_zeropagex_2(cpu: &mut MOS6502) -> u16 {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    let address_x = (address + cpu.reg.ix as u16) & 0xFF;
-    cpu.reg.pc += 1;
+fn fetch_zeropagex_2<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.base_address = cpu.read(cpu.reg.pc)? as u16;
+        cpu.address = (cpu.base_address + cpu.reg.ix as u16) & 0xFF;
+        cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+    } else {
+        // T2
+        cpu.read(cpu.base_address)?;
+    }
     cpu.tick();
-    // T2
-    cpu.read(address);
-    cpu.tick();
-
-    address_x
+    Ok(Cycle::Next)
 }
 
-pub fn zeropagex_3write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2
-    let address_x = fetch_zeropagex_2(cpu);
-    // T3
-    let value = func(cpu);
-    cpu.write(address_x, value);
-    cpu.tick();
+pub fn zeropagex_3write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_zeropagex_2(cpu),
+        // T3
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn zeropagex_3read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2
-    let address_x = fetch_zeropagex_2(cpu);
-    // T3
-    let value = cpu.read(address_x);
-    func(cpu, value);
-    cpu.tick();
+pub fn zeropagex_3read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_zeropagex_2(cpu),
+        // T3
+        _ => read_cycle(cpu, func),
+    }
 }
 
-pub fn zeropagex_5rmw(cpu: &mut MOS6502, func: &ReadWriteInst) {
-    // T1, T2
-    let address_x = fetch_zeropagex_2(cpu);
-    // T3
-    let value = cpu.read(address_x);
-    cpu.tick();
-    // T4
-    cpu.write(address_x, value);
-    cpu.tick();
-    // T5
-    let value = func(cpu, value);
-    cpu.write(address_x, value);
-    cpu.tick();
+pub fn zeropagex_5rmw<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadWriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_zeropagex_2(cpu),
+        // T3, T4, T5
+        cycle => read_modify_write_cycle(cpu, func, cycle - 2),
+    }
 }
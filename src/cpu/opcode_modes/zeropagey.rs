@@ -5,33 +5,34 @@ use crate::cpu::MOS6502;
 // ZeroPage,Y Addressing //
 ///////////////////////////
 
-fn fetch_zeropagey_2(cpu: &mut MOS6502) -> u16 {
-    // T1
-    let address = cpu.read(cpu.reg.pc) as u16;
-    let address_y = (address + cpu.reg.iy as u16) & 0xFF;
-    cpu.reg.pc += 1;
+fn fetch_zeropagey_2<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    if cpu.instruction_cycle == 1 {
+        // T1
+        cpu.base_address = cpu.read(cpu.reg.pc)? as u16;
+        cpu.address = (cpu.base_address + cpu.reg.iy as u16) & 0xFF;
+        cpu.reg.pc = cpu.reg.pc.wrapping_add(1);
+    } else {
+        // T2
+        cpu.read(cpu.base_address)?;
+    }
     cpu.tick();
-    // T2
-    cpu.read(address);
-    cpu.tick();
-
-    address_y
+    Ok(Cycle::Next)
 }
 
-pub fn zeropagey_3write(cpu: &mut MOS6502, func: &WriteInst) {
-    // T1, T2
-    let address_y = fetch_zeropagey_2(cpu);
-    // T3
-    let value = func(cpu);
-    cpu.write(address_y, value);
-    cpu.tick();
+pub fn zeropagey_3write<B: AddressBus>(cpu: &mut MOS6502<B>, func: WriteInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_zeropagey_2(cpu),
+        // T3
+        _ => write_cycle(cpu, func),
+    }
 }
 
-pub fn zeropagey_3read(cpu: &mut MOS6502, func: &ReadInst) {
-    // T1, T2
-    let address_y = fetch_zeropagey_2(cpu);
-    // T3
-    let value = cpu.read(address_y);
-    func(cpu, value);
-    cpu.tick();
+pub fn zeropagey_3read<B: AddressBus>(cpu: &mut MOS6502<B>, func: ReadInst<B>) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1, T2
+        1 | 2 => fetch_zeropagey_2(cpu),
+        // T3
+        _ => read_cycle(cpu, func),
+    }
 }
@@ -0,0 +1,455 @@
+use super::arithmetic_instructions::*;
+use super::branching_instructions::*;
+use super::illegal_instructions::*;
+use super::inc_dec_instructions::*;
+use super::logical_instructions::*;
+use super::opcode_modes::*;
+use super::stack_instructions::*;
+use super::status_instructions::*;
+use super::transfer_load_store_instructions::*;
+use super::{CpuVariant, CycleResult, MOS6502};
+use crate::address_bus::AddressBus;
+
+use AddressingMode::*;
+use Handler::*;
+
+// How the instruction is run once the opcode has been fetched
+pub enum Handler<B: AddressBus> {
+    FetchOnly,         // Done with the opcode fetch, like the 65C02 one cycle NOPs
+    Internal(Inst<B>), // A dummy read of the next byte then the operation
+    Read(ReadInst<B>),
+    Write(WriteInst<B>),
+    ReadModifyWrite(ReadWriteInst<B>),
+    IndexedShift(ReadWriteInst<B>), // 65C02 abs,X shifts, the fixup cycle only on a page crossing
+    UnstableWrite(UnstableWriteInst<B>),
+    Branch(BranchInst<B>),
+    Custom(CycleInst<B>), // Drives every remaining cycle itself, one per call
+}
+
+pub struct Opcode<B: AddressBus> {
+    pub mnemonic: &'static str,
+    pub mode: AddressingMode,
+    pub cycles: u8, // Without page crossings, taken branches or the 65C02 decimal cycle
+    pub handler: Handler<B>,
+}
+
+// Derive would require the bus to be Clone and Copy too
+impl<B: AddressBus> Clone for Handler<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B: AddressBus> Copy for Handler<B> {}
+
+impl<B: AddressBus> Clone for Opcode<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B: AddressBus> Copy for Opcode<B> {}
+
+const fn op<B: AddressBus>(
+    mnemonic: &'static str,
+    mode: AddressingMode,
+    cycles: u8,
+    handler: Handler<B>,
+) -> Opcode<B> {
+    Opcode {
+        mnemonic,
+        mode,
+        cycles,
+        handler,
+    }
+}
+
+// Built at compile time for every bus type. Returned by value so the bus doesn't have to be
+// 'static, the CPU keeps its own copy
+pub fn opcode_table<B: AddressBus>(variant: CpuVariant) -> [Opcode<B>; 256] {
+    match variant {
+        CpuVariant::Nmos6502 | CpuVariant::Ricoh2A03 => const { nmos_opcodes() },
+        CpuVariant::Cmos65SC02 => const { cmos_opcodes(false, false) },
+        CpuVariant::Cmos65C02 => const { cmos_opcodes(true, false) },
+        CpuVariant::W65C02S => const { cmos_opcodes(true, true) },
+    }
+}
+
+// Runs the next cycle of the instruction after its opcode fetch
+#[inline(always)]
+pub fn execute<B: AddressBus>(cpu: &mut MOS6502<B>, opcode: &Opcode<B>) -> CycleResult {
+    match opcode.handler {
+        FetchOnly => cpu.unsupported("cycle after the opcode fetch"),
+        Internal(func) => instruction_implied(cpu, func),
+        Read(func) => instruction_read(cpu, opcode.mode, func),
+        Write(func) => instruction_write(cpu, opcode.mode, func),
+        ReadModifyWrite(func) => instruction_read_move_write(cpu, opcode.mode, func),
+        IndexedShift(func) => instruction_indexed_shift(cpu, opcode.mode, func),
+        UnstableWrite(func) => instruction_write_unstable(cpu, opcode.mode, func),
+        Branch(func) => instruction_branch(cpu, func),
+        Custom(func) => func(cpu),
+    }
+}
+
+// JAMs never finish so they have no cycle count
+#[rustfmt::skip]
+const fn nmos_opcodes<B: AddressBus>() -> [Opcode<B>; 256] {
+    let mut table = [op("JAM", Implied, 0, Custom(jam)); 256];
+    table[0x69] = op("ADC", Immediate, 2, Read(add_with_carry));
+    table[0x65] = op("ADC", ZeroPage, 3, Read(add_with_carry));
+    table[0x75] = op("ADC", ZeroPageX, 4, Read(add_with_carry));
+    table[0x6D] = op("ADC", Absolute, 4, Read(add_with_carry));
+    table[0x7D] = op("ADC", AbsoluteX, 4, Read(add_with_carry));
+    table[0x79] = op("ADC", AbsoluteY, 4, Read(add_with_carry));
+    table[0x61] = op("ADC", IndirectX, 6, Read(add_with_carry));
+    table[0x71] = op("ADC", IndirectY, 5, Read(add_with_carry));
+    table[0x29] = op("AND", Immediate, 2, Read(logical_and));
+    table[0x25] = op("AND", ZeroPage, 3, Read(logical_and));
+    table[0x35] = op("AND", ZeroPageX, 4, Read(logical_and));
+    table[0x2D] = op("AND", Absolute, 4, Read(logical_and));
+    table[0x3D] = op("AND", AbsoluteX, 4, Read(logical_and));
+    table[0x39] = op("AND", AbsoluteY, 4, Read(logical_and));
+    table[0x21] = op("AND", IndirectX, 6, Read(logical_and));
+    table[0x31] = op("AND", IndirectY, 5, Read(logical_and));
+    table[0x0A] = op("ASL", Accumulator, 2, ReadModifyWrite(logical_shift_left));
+    table[0x06] = op("ASL", ZeroPage, 5, ReadModifyWrite(logical_shift_left));
+    table[0x16] = op("ASL", ZeroPageX, 6, ReadModifyWrite(logical_shift_left));
+    table[0x0E] = op("ASL", Absolute, 6, ReadModifyWrite(logical_shift_left));
+    table[0x1E] = op("ASL", AbsoluteX, 7, ReadModifyWrite(logical_shift_left));
+    table[0x90] = op("BCC", Relative, 2, Branch(branch_if_carry_clear));
+    table[0xB0] = op("BCS", Relative, 2, Branch(branch_if_carry_set));
+    table[0xF0] = op("BEQ", Relative, 2, Branch(branch_if_equal));
+    table[0x24] = op("BIT", ZeroPage, 3, Read(logical_bit_test));
+    table[0x2C] = op("BIT", Absolute, 4, Read(logical_bit_test));
+    table[0x30] = op("BMI", Relative, 2, Branch(branch_if_minus));
+    table[0xD0] = op("BNE", Relative, 2, Branch(branch_if_not_equal));
+    table[0x10] = op("BPL", Relative, 2, Branch(branch_if_positive));
+    table[0x00] = op("BRK", Implied, 7, Custom(break_interrupt));
+    table[0x50] = op("BVC", Relative, 2, Branch(branch_if_overflow_clear));
+    table[0x70] = op("BVS", Relative, 2, Branch(branch_if_overflow_set));
+    table[0x18] = op("CLC", Implied, 2, Internal(clear_carry));
+    table[0xD8] = op("CLD", Implied, 2, Internal(clear_decimal));
+    table[0x58] = op("CLI", Implied, 2, Internal(clear_int_disable));
+    table[0xB8] = op("CLV", Implied, 2, Internal(clear_overflow));
+    table[0xC9] = op("CMP", Immediate, 2, Read(compare_ac));
+    table[0xC5] = op("CMP", ZeroPage, 3, Read(compare_ac));
+    table[0xD5] = op("CMP", ZeroPageX, 4, Read(compare_ac));
+    table[0xCD] = op("CMP", Absolute, 4, Read(compare_ac));
+    table[0xDD] = op("CMP", AbsoluteX, 4, Read(compare_ac));
+    table[0xD9] = op("CMP", AbsoluteY, 4, Read(compare_ac));
+    table[0xC1] = op("CMP", IndirectX, 6, Read(compare_ac));
+    table[0xD1] = op("CMP", IndirectY, 5, Read(compare_ac));
+    table[0xE0] = op("CPX", Immediate, 2, Read(compare_ix));
+    table[0xE4] = op("CPX", ZeroPage, 3, Read(compare_ix));
+    table[0xEC] = op("CPX", Absolute, 4, Read(compare_ix));
+    table[0xC0] = op("CPY", Immediate, 2, Read(compare_iy));
+    table[0xC4] = op("CPY", ZeroPage, 3, Read(compare_iy));
+    table[0xCC] = op("CPY", Absolute, 4, Read(compare_iy));
+    table[0xC6] = op("DEC", ZeroPage, 5, ReadModifyWrite(dec_memory));
+    table[0xD6] = op("DEC", ZeroPageX, 6, ReadModifyWrite(dec_memory));
+    table[0xCE] = op("DEC", Absolute, 6, ReadModifyWrite(dec_memory));
+    table[0xDE] = op("DEC", AbsoluteX, 7, ReadModifyWrite(dec_memory));
+    table[0xCA] = op("DEX", Implied, 2, Internal(dec_ix));
+    table[0x88] = op("DEY", Implied, 2, Internal(dec_iy));
+    table[0x49] = op("EOR", Immediate, 2, Read(logical_exclusive_or));
+    table[0x45] = op("EOR", ZeroPage, 3, Read(logical_exclusive_or));
+    table[0x55] = op("EOR", ZeroPageX, 4, Read(logical_exclusive_or));
+    table[0x4D] = op("EOR", Absolute, 4, Read(logical_exclusive_or));
+    table[0x5D] = op("EOR", AbsoluteX, 4, Read(logical_exclusive_or));
+    table[0x59] = op("EOR", AbsoluteY, 4, Read(logical_exclusive_or));
+    table[0x41] = op("EOR", IndirectX, 6, Read(logical_exclusive_or));
+    table[0x51] = op("EOR", IndirectY, 5, Read(logical_exclusive_or));
+    table[0xE6] = op("INC", ZeroPage, 5, ReadModifyWrite(inc_memory));
+    table[0xF6] = op("INC", ZeroPageX, 6, ReadModifyWrite(inc_memory));
+    table[0xEE] = op("INC", Absolute, 6, ReadModifyWrite(inc_memory));
+    table[0xFE] = op("INC", AbsoluteX, 7, ReadModifyWrite(inc_memory));
+    table[0xE8] = op("INX", Implied, 2, Internal(inc_ix));
+    table[0xC8] = op("INY", Implied, 2, Internal(inc_iy));
+    table[0x4C] = op("JMP", Absolute, 3, Custom(jmp_absolute));
+    table[0x6C] = op("JMP", Indirect, 5, Custom(jmp_indirect));
+    table[0x20] = op("JSR", Absolute, 6, Custom(jump_to_subroutine));
+    table[0xA9] = op("LDA", Immediate, 2, Read(load_ac));
+    table[0xA5] = op("LDA", ZeroPage, 3, Read(load_ac));
+    table[0xB5] = op("LDA", ZeroPageX, 4, Read(load_ac));
+    table[0xAD] = op("LDA", Absolute, 4, Read(load_ac));
+    table[0xBD] = op("LDA", AbsoluteX, 4, Read(load_ac));
+    table[0xB9] = op("LDA", AbsoluteY, 4, Read(load_ac));
+    table[0xA1] = op("LDA", IndirectX, 6, Read(load_ac));
+    table[0xB1] = op("LDA", IndirectY, 5, Read(load_ac));
+    table[0xA2] = op("LDX", Immediate, 2, Read(load_ix));
+    table[0xA6] = op("LDX", ZeroPage, 3, Read(load_ix));
+    table[0xB6] = op("LDX", ZeroPageY, 4, Read(load_ix));
+    table[0xAE] = op("LDX", Absolute, 4, Read(load_ix));
+    table[0xBE] = op("LDX", AbsoluteY, 4, Read(load_ix));
+    table[0xA0] = op("LDY", Immediate, 2, Read(load_iy));
+    table[0xA4] = op("LDY", ZeroPage, 3, Read(load_iy));
+    table[0xB4] = op("LDY", ZeroPageX, 4, Read(load_iy));
+    table[0xAC] = op("LDY", Absolute, 4, Read(load_iy));
+    table[0xBC] = op("LDY", AbsoluteX, 4, Read(load_iy));
+    table[0x4A] = op("LSR", Accumulator, 2, ReadModifyWrite(logical_shift_right));
+    table[0x46] = op("LSR", ZeroPage, 5, ReadModifyWrite(logical_shift_right));
+    table[0x56] = op("LSR", ZeroPageX, 6, ReadModifyWrite(logical_shift_right));
+    table[0x4E] = op("LSR", Absolute, 6, ReadModifyWrite(logical_shift_right));
+    table[0x5E] = op("LSR", AbsoluteX, 7, ReadModifyWrite(logical_shift_right));
+    table[0xEA] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0x09] = op("ORA", Immediate, 2, Read(logical_inclusive_or));
+    table[0x05] = op("ORA", ZeroPage, 3, Read(logical_inclusive_or));
+    table[0x15] = op("ORA", ZeroPageX, 4, Read(logical_inclusive_or));
+    table[0x0D] = op("ORA", Absolute, 4, Read(logical_inclusive_or));
+    table[0x1D] = op("ORA", AbsoluteX, 4, Read(logical_inclusive_or));
+    table[0x19] = op("ORA", AbsoluteY, 4, Read(logical_inclusive_or));
+    table[0x01] = op("ORA", IndirectX, 6, Read(logical_inclusive_or));
+    table[0x11] = op("ORA", IndirectY, 5, Read(logical_inclusive_or));
+    table[0x48] = op("PHA", Implied, 3, Custom(push_ac));
+    table[0x08] = op("PHP", Implied, 3, Custom(push_processor));
+    table[0x68] = op("PLA", Implied, 4, Custom(pull_ac));
+    table[0x28] = op("PLP", Implied, 4, Custom(pull_processor_status));
+    table[0x2A] = op("ROL", Accumulator, 2, ReadModifyWrite(logical_rotate_left));
+    table[0x26] = op("ROL", ZeroPage, 5, ReadModifyWrite(logical_rotate_left));
+    table[0x36] = op("ROL", ZeroPageX, 6, ReadModifyWrite(logical_rotate_left));
+    table[0x2E] = op("ROL", Absolute, 6, ReadModifyWrite(logical_rotate_left));
+    table[0x3E] = op("ROL", AbsoluteX, 7, ReadModifyWrite(logical_rotate_left));
+    table[0x6A] = op("ROR", Accumulator, 2, ReadModifyWrite(logical_rotate_right));
+    table[0x66] = op("ROR", ZeroPage, 5, ReadModifyWrite(logical_rotate_right));
+    table[0x76] = op("ROR", ZeroPageX, 6, ReadModifyWrite(logical_rotate_right));
+    table[0x6E] = op("ROR", Absolute, 6, ReadModifyWrite(logical_rotate_right));
+    table[0x7E] = op("ROR", AbsoluteX, 7, ReadModifyWrite(logical_rotate_right));
+    table[0x40] = op("RTI", Implied, 6, Custom(return_from_interrupt));
+    table[0x60] = op("RTS", Implied, 6, Custom(return_from_subroutine));
+    table[0xE9] = op("SBC", Immediate, 2, Read(sub_with_carry));
+    table[0xE5] = op("SBC", ZeroPage, 3, Read(sub_with_carry));
+    table[0xF5] = op("SBC", ZeroPageX, 4, Read(sub_with_carry));
+    table[0xED] = op("SBC", Absolute, 4, Read(sub_with_carry));
+    table[0xFD] = op("SBC", AbsoluteX, 4, Read(sub_with_carry));
+    table[0xF9] = op("SBC", AbsoluteY, 4, Read(sub_with_carry));
+    table[0xE1] = op("SBC", IndirectX, 6, Read(sub_with_carry));
+    table[0xF1] = op("SBC", IndirectY, 5, Read(sub_with_carry));
+    table[0x38] = op("SEC", Implied, 2, Internal(set_carry));
+    table[0xF8] = op("SED", Implied, 2, Internal(set_decimal));
+    table[0x78] = op("SEI", Implied, 2, Internal(set_int_disable));
+    table[0x85] = op("STA", ZeroPage, 3, Write(store_ac));
+    table[0x95] = op("STA", ZeroPageX, 4, Write(store_ac));
+    table[0x8D] = op("STA", Absolute, 4, Write(store_ac));
+    table[0x9D] = op("STA", AbsoluteX, 5, Write(store_ac));
+    table[0x99] = op("STA", AbsoluteY, 5, Write(store_ac));
+    table[0x81] = op("STA", IndirectX, 6, Write(store_ac));
+    table[0x91] = op("STA", IndirectY, 6, Write(store_ac));
+    table[0x86] = op("STX", ZeroPage, 3, Write(store_ix));
+    table[0x96] = op("STX", ZeroPageY, 4, Write(store_ix));
+    table[0x8E] = op("STX", Absolute, 4, Write(store_ix));
+    table[0x84] = op("STY", ZeroPage, 3, Write(store_iy));
+    table[0x94] = op("STY", ZeroPageX, 4, Write(store_iy));
+    table[0x8C] = op("STY", Absolute, 4, Write(store_iy));
+    table[0xAA] = op("TAX", Implied, 2, Internal(transfer_ac_to_x));
+    table[0xA8] = op("TAY", Implied, 2, Internal(transfer_ac_to_y));
+    table[0xBA] = op("TSX", Implied, 2, Internal(transfer_sp_to_x));
+    table[0x8A] = op("TXA", Implied, 2, Internal(transfer_x_to_ac));
+    table[0x9A] = op("TXS", Implied, 2, Internal(transfer_x_to_sp));
+    table[0x98] = op("TYA", Implied, 2, Internal(transfer_y_to_ac));
+
+    // Undocumented
+    table[0x4B] = op("ALR", Immediate, 2, Read(and_shift_right));
+    table[0x0B] = op("ANC", Immediate, 2, Read(and_carry));
+    table[0x2B] = op("ANC", Immediate, 2, Read(and_carry));
+    table[0x6B] = op("ARR", Immediate, 2, Read(and_rotate_right));
+    table[0xC7] = op("DCP", ZeroPage, 5, ReadModifyWrite(dec_compare));
+    table[0xD7] = op("DCP", ZeroPageX, 6, ReadModifyWrite(dec_compare));
+    table[0xCF] = op("DCP", Absolute, 6, ReadModifyWrite(dec_compare));
+    table[0xDF] = op("DCP", AbsoluteX, 7, ReadModifyWrite(dec_compare));
+    table[0xDB] = op("DCP", AbsoluteY, 7, ReadModifyWrite(dec_compare));
+    table[0xC3] = op("DCP", IndirectX, 8, ReadModifyWrite(dec_compare));
+    table[0xD3] = op("DCP", IndirectY, 8, ReadModifyWrite(dec_compare));
+    table[0xE7] = op("ISC", ZeroPage, 5, ReadModifyWrite(inc_sub));
+    table[0xF7] = op("ISC", ZeroPageX, 6, ReadModifyWrite(inc_sub));
+    table[0xEF] = op("ISC", Absolute, 6, ReadModifyWrite(inc_sub));
+    table[0xFF] = op("ISC", AbsoluteX, 7, ReadModifyWrite(inc_sub));
+    table[0xFB] = op("ISC", AbsoluteY, 7, ReadModifyWrite(inc_sub));
+    table[0xE3] = op("ISC", IndirectX, 8, ReadModifyWrite(inc_sub));
+    table[0xF3] = op("ISC", IndirectY, 8, ReadModifyWrite(inc_sub));
+    table[0x02] = op("JAM", Implied, 0, Custom(jam));
+    table[0x12] = op("JAM", Implied, 0, Custom(jam));
+    table[0x22] = op("JAM", Implied, 0, Custom(jam));
+    table[0x32] = op("JAM", Implied, 0, Custom(jam));
+    table[0x42] = op("JAM", Implied, 0, Custom(jam));
+    table[0x52] = op("JAM", Implied, 0, Custom(jam));
+    table[0x62] = op("JAM", Implied, 0, Custom(jam));
+    table[0x72] = op("JAM", Implied, 0, Custom(jam));
+    table[0x92] = op("JAM", Implied, 0, Custom(jam));
+    table[0xB2] = op("JAM", Implied, 0, Custom(jam));
+    table[0xD2] = op("JAM", Implied, 0, Custom(jam));
+    table[0xF2] = op("JAM", Implied, 0, Custom(jam));
+    table[0xBB] = op("LAS", AbsoluteY, 4, Read(load_ac_ix_sp));
+    table[0xA7] = op("LAX", ZeroPage, 3, Read(load_ac_ix));
+    table[0xB7] = op("LAX", ZeroPageY, 4, Read(load_ac_ix));
+    table[0xAF] = op("LAX", Absolute, 4, Read(load_ac_ix));
+    table[0xBF] = op("LAX", AbsoluteY, 4, Read(load_ac_ix));
+    table[0xA3] = op("LAX", IndirectX, 6, Read(load_ac_ix));
+    table[0xB3] = op("LAX", IndirectY, 5, Read(load_ac_ix));
+    table[0xAB] = op("LXA", Immediate, 2, Read(load_ac_ix_and));
+    table[0x1A] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0x3A] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0x5A] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0x7A] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0xDA] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0xFA] = op("NOP", Implied, 2, Internal(no_operation));
+    table[0x80] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x82] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x89] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0xC2] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0xE2] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x04] = op("NOP", ZeroPage, 3, Read(no_operation_read));
+    table[0x44] = op("NOP", ZeroPage, 3, Read(no_operation_read));
+    table[0x64] = op("NOP", ZeroPage, 3, Read(no_operation_read));
+    table[0x14] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0x34] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0x54] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0x74] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0xD4] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0xF4] = op("NOP", ZeroPageX, 4, Read(no_operation_read));
+    table[0x0C] = op("NOP", Absolute, 4, Read(no_operation_read));
+    table[0x1C] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0x3C] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0x5C] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0x7C] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0xDC] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0xFC] = op("NOP", AbsoluteX, 4, Read(no_operation_read));
+    table[0x27] = op("RLA", ZeroPage, 5, ReadModifyWrite(rotate_left_and));
+    table[0x37] = op("RLA", ZeroPageX, 6, ReadModifyWrite(rotate_left_and));
+    table[0x2F] = op("RLA", Absolute, 6, ReadModifyWrite(rotate_left_and));
+    table[0x3F] = op("RLA", AbsoluteX, 7, ReadModifyWrite(rotate_left_and));
+    table[0x3B] = op("RLA", AbsoluteY, 7, ReadModifyWrite(rotate_left_and));
+    table[0x23] = op("RLA", IndirectX, 8, ReadModifyWrite(rotate_left_and));
+    table[0x33] = op("RLA", IndirectY, 8, ReadModifyWrite(rotate_left_and));
+    table[0x67] = op("RRA", ZeroPage, 5, ReadModifyWrite(rotate_right_add));
+    table[0x77] = op("RRA", ZeroPageX, 6, ReadModifyWrite(rotate_right_add));
+    table[0x6F] = op("RRA", Absolute, 6, ReadModifyWrite(rotate_right_add));
+    table[0x7F] = op("RRA", AbsoluteX, 7, ReadModifyWrite(rotate_right_add));
+    table[0x7B] = op("RRA", AbsoluteY, 7, ReadModifyWrite(rotate_right_add));
+    table[0x63] = op("RRA", IndirectX, 8, ReadModifyWrite(rotate_right_add));
+    table[0x73] = op("RRA", IndirectY, 8, ReadModifyWrite(rotate_right_add));
+    table[0x87] = op("SAX", ZeroPage, 3, Write(store_ac_and_ix));
+    table[0x97] = op("SAX", ZeroPageY, 4, Write(store_ac_and_ix));
+    table[0x8F] = op("SAX", Absolute, 4, Write(store_ac_and_ix));
+    table[0x83] = op("SAX", IndirectX, 6, Write(store_ac_and_ix));
+    table[0xEB] = op("SBC", Immediate, 2, Read(sub_with_carry));
+    table[0xCB] = op("SBX", Immediate, 2, Read(and_ix_sub));
+    table[0x93] = op("SHA", IndirectY, 6, UnstableWrite(store_ac_and_ix_and_high));
+    table[0x9F] = op("SHA", AbsoluteY, 5, UnstableWrite(store_ac_and_ix_and_high));
+    table[0x9E] = op("SHX", AbsoluteY, 5, UnstableWrite(store_ix_and_high));
+    table[0x9C] = op("SHY", AbsoluteX, 5, UnstableWrite(store_iy_and_high));
+    table[0x07] = op("SLO", ZeroPage, 5, ReadModifyWrite(shift_left_or));
+    table[0x17] = op("SLO", ZeroPageX, 6, ReadModifyWrite(shift_left_or));
+    table[0x0F] = op("SLO", Absolute, 6, ReadModifyWrite(shift_left_or));
+    table[0x1F] = op("SLO", AbsoluteX, 7, ReadModifyWrite(shift_left_or));
+    table[0x1B] = op("SLO", AbsoluteY, 7, ReadModifyWrite(shift_left_or));
+    table[0x03] = op("SLO", IndirectX, 8, ReadModifyWrite(shift_left_or));
+    table[0x13] = op("SLO", IndirectY, 8, ReadModifyWrite(shift_left_or));
+    table[0x47] = op("SRE", ZeroPage, 5, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x57] = op("SRE", ZeroPageX, 6, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x4F] = op("SRE", Absolute, 6, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x5F] = op("SRE", AbsoluteX, 7, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x5B] = op("SRE", AbsoluteY, 7, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x43] = op("SRE", IndirectX, 8, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x53] = op("SRE", IndirectY, 8, ReadModifyWrite(shift_right_exclusive_or));
+    table[0x9B] = op("TAS", AbsoluteY, 5, UnstableWrite(transfer_ac_and_ix_to_sp_and_store));
+    table[0x8B] = op("XAA", Immediate, 2, Read(transfer_ix_to_ac_and));
+
+    table
+}
+
+// The 65C02 reuses every opcode that is undocumented on the NMOS 6502
+#[rustfmt::skip]
+const fn cmos_opcodes<B: AddressBus>(bit_instructions: bool, wait_and_stop: bool) -> [Opcode<B>; 256] {
+    let mut table = nmos_opcodes::<B>();
+
+    // Unused opcodes in the x3, x7, xB and xF columns are single byte, single cycle NOPs
+    let mut opcode = 0;
+    while opcode < 256 {
+        if (opcode & 0b11) == 0b11 {
+            table[opcode] = op("NOP", Implied, 1, FetchOnly);
+        }
+        opcode += 1;
+    }
+
+    table[0x72] = op("ADC", ZeroPageIndirect, 5, Read(add_with_carry));
+    table[0x32] = op("AND", ZeroPageIndirect, 5, Read(logical_and));
+    table[0x34] = op("BIT", ZeroPageX, 4, Read(logical_bit_test));
+    table[0x3C] = op("BIT", AbsoluteX, 4, Read(logical_bit_test));
+    table[0x89] = op("BIT", Immediate, 2, Read(logical_bit_test_immediate));
+    table[0x80] = op("BRA", Relative, 3, Branch(branch_always));
+    table[0xD2] = op("CMP", ZeroPageIndirect, 5, Read(compare_ac));
+    table[0x3A] = op("DEC", Accumulator, 2, ReadModifyWrite(dec_memory));
+    table[0x1E] = op("ASL", AbsoluteX, 6, IndexedShift(logical_shift_left));
+    table[0x5E] = op("LSR", AbsoluteX, 6, IndexedShift(logical_shift_right));
+    table[0x3E] = op("ROL", AbsoluteX, 6, IndexedShift(logical_rotate_left));
+    table[0x7E] = op("ROR", AbsoluteX, 6, IndexedShift(logical_rotate_right));
+    table[0x52] = op("EOR", ZeroPageIndirect, 5, Read(logical_exclusive_or));
+    table[0x1A] = op("INC", Accumulator, 2, ReadModifyWrite(inc_memory));
+    table[0x6C] = op("JMP", Indirect, 6, Custom(jmp_indirect)); // Extra cycle, no page wrap bug
+    table[0x7C] = op("JMP", AbsoluteIndexedIndirect, 6, Custom(jmp_absolute_indexed_indirect));
+    table[0xB2] = op("LDA", ZeroPageIndirect, 5, Read(load_ac));
+    table[0x02] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x22] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x42] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0x5C] = op("NOP", Absolute, 8, Custom(no_operation_long));
+    table[0x62] = op("NOP", Immediate, 2, Read(no_operation_read));
+    table[0xDC] = op("NOP", Absolute, 4, Read(no_operation_read));
+    table[0xFC] = op("NOP", Absolute, 4, Read(no_operation_read));
+    table[0x12] = op("ORA", ZeroPageIndirect, 5, Read(logical_inclusive_or));
+    table[0xDA] = op("PHX", Implied, 3, Custom(push_ix));
+    table[0x5A] = op("PHY", Implied, 3, Custom(push_iy));
+    table[0xFA] = op("PLX", Implied, 4, Custom(pull_ix));
+    table[0x7A] = op("PLY", Implied, 4, Custom(pull_iy));
+    table[0xF2] = op("SBC", ZeroPageIndirect, 5, Read(sub_with_carry));
+    table[0x92] = op("STA", ZeroPageIndirect, 5, Write(store_ac));
+    table[0x64] = op("STZ", ZeroPage, 3, Write(store_zero));
+    table[0x74] = op("STZ", ZeroPageX, 4, Write(store_zero));
+    table[0x9C] = op("STZ", Absolute, 4, Write(store_zero));
+    table[0x9E] = op("STZ", AbsoluteX, 5, Write(store_zero));
+    table[0x14] = op("TRB", ZeroPage, 5, ReadModifyWrite(test_and_reset_bits));
+    table[0x1C] = op("TRB", Absolute, 6, ReadModifyWrite(test_and_reset_bits));
+    table[0x04] = op("TSB", ZeroPage, 5, ReadModifyWrite(test_and_set_bits));
+    table[0x0C] = op("TSB", Absolute, 6, ReadModifyWrite(test_and_set_bits));
+
+    // Rockwell and WDC extensions
+    if bit_instructions {
+        table[0x0F] = op("BBR0", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<0, B>));
+        table[0x1F] = op("BBR1", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<1, B>));
+        table[0x2F] = op("BBR2", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<2, B>));
+        table[0x3F] = op("BBR3", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<3, B>));
+        table[0x4F] = op("BBR4", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<4, B>));
+        table[0x5F] = op("BBR5", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<5, B>));
+        table[0x6F] = op("BBR6", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<6, B>));
+        table[0x7F] = op("BBR7", ZeroPageRelative, 5, Custom(branch_on_bit_reset::<7, B>));
+        table[0x8F] = op("BBS0", ZeroPageRelative, 5, Custom(branch_on_bit_set::<0, B>));
+        table[0x9F] = op("BBS1", ZeroPageRelative, 5, Custom(branch_on_bit_set::<1, B>));
+        table[0xAF] = op("BBS2", ZeroPageRelative, 5, Custom(branch_on_bit_set::<2, B>));
+        table[0xBF] = op("BBS3", ZeroPageRelative, 5, Custom(branch_on_bit_set::<3, B>));
+        table[0xCF] = op("BBS4", ZeroPageRelative, 5, Custom(branch_on_bit_set::<4, B>));
+        table[0xDF] = op("BBS5", ZeroPageRelative, 5, Custom(branch_on_bit_set::<5, B>));
+        table[0xEF] = op("BBS6", ZeroPageRelative, 5, Custom(branch_on_bit_set::<6, B>));
+        table[0xFF] = op("BBS7", ZeroPageRelative, 5, Custom(branch_on_bit_set::<7, B>));
+        table[0x07] = op("RMB0", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<0, B>));
+        table[0x17] = op("RMB1", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<1, B>));
+        table[0x27] = op("RMB2", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<2, B>));
+        table[0x37] = op("RMB3", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<3, B>));
+        table[0x47] = op("RMB4", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<4, B>));
+        table[0x57] = op("RMB5", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<5, B>));
+        table[0x67] = op("RMB6", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<6, B>));
+        table[0x77] = op("RMB7", ZeroPage, 5, ReadModifyWrite(reset_memory_bit::<7, B>));
+        table[0x87] = op("SMB0", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<0, B>));
+        table[0x97] = op("SMB1", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<1, B>));
+        table[0xA7] = op("SMB2", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<2, B>));
+        table[0xB7] = op("SMB3", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<3, B>));
+        table[0xC7] = op("SMB4", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<4, B>));
+        table[0xD7] = op("SMB5", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<5, B>));
+        table[0xE7] = op("SMB6", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<6, B>));
+        table[0xF7] = op("SMB7", ZeroPage, 5, ReadModifyWrite(set_memory_bit::<7, B>));
+    }
+    if wait_and_stop {
+        table[0xDB] = op("STP", Implied, 3, Custom(stop));
+        table[0xCB] = op("WAI", Implied, 3, Custom(wait_for_interrupt));
+    }
+
+    table
+}
@@ -0,0 +1,57 @@
+use crate::cpu::*;
+use std::collections::VecDeque;
+
+const ADDRESSES: usize = (u16::MAX as usize) + 1;
+
+// The last writes the CPU did to every address, for finding which instruction clobbered a
+// value. Writes made through cpu.bus from outside aren't seen
+pub struct Provenance {
+    history: usize,                     // Writes kept per address
+    writes: Vec<VecDeque<WriteRecord>>, // Per address, oldest first
+}
+
+impl Provenance {
+    fn new(history: usize) -> Self {
+        Self {
+            history: history.max(1),
+            writes: vec![VecDeque::new(); ADDRESSES],
+        }
+    }
+
+    pub fn record_write(&mut self, write: WriteRecord) {
+        let writes = &mut self.writes[write.address as usize];
+        if writes.len() == self.history {
+            writes.pop_front();
+        }
+        writes.push_back(write);
+    }
+
+    // Forgets the writes from the given cycle on, after rewinding to it
+    pub fn truncate(&mut self, cycles: u64) {
+        for writes in &mut self.writes {
+            while writes.back().is_some_and(|write| write.cycle >= cycles) {
+                writes.pop_back();
+            }
+        }
+    }
+}
+
+impl<B: AddressBus> MOS6502<B> {
+    // Starts tracking the given number of writes to every address (or stops with None), 1
+    // only keeps the last one
+    pub fn set_write_tracking(&mut self, history: Option<usize>) {
+        self.provenance = history.map(Provenance::new);
+    }
+
+    // Who last wrote to the address, None when nothing did since tracking started
+    pub fn last_write(&self, address: u16) -> Option<WriteRecord> {
+        self.write_history(address).next().copied()
+    }
+
+    // The tracked writes to the address, newest first
+    pub fn write_history(&self, address: u16) -> impl Iterator<Item = &WriteRecord> {
+        self.provenance
+            .iter()
+            .flat_map(move |provenance| provenance.writes[address as usize].iter().rev())
+    }
+}
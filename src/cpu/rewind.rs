@@ -0,0 +1,247 @@
+use crate::cpu::*;
+use std::collections::VecDeque;
+
+// Rewinding loads the last snapshot before the target and replays from there, checking the
+// replay writes exactly what the journal says the first run did. Changes made from outside
+// between steps (registers, writes through cpu.bus, line changes) aren't journaled so the
+// history only covers what the CPU did on its own, set_pc(), interrupt() and load_state()
+// start a new history
+pub struct Rewind {
+    policy: RewindPolicy,
+    snapshots: VecDeque<(u64, Vec<u8>)>, // Save states and the cycle they were taken at
+    instructions: VecDeque<(u64, u16)>,  // Cycle and PC each instruction (or interrupt) began at
+    writes: VecDeque<WriteRecord>,
+    replay: Option<usize>, // The next journaled write a replay should do
+    diverged: Option<u64>, // The cycle a replay first wrote something else
+}
+
+impl Rewind {
+    fn new(policy: RewindPolicy) -> Self {
+        Self {
+            policy,
+            snapshots: VecDeque::new(),
+            instructions: VecDeque::new(),
+            writes: VecDeque::new(),
+            replay: None,
+            diverged: None,
+        }
+    }
+
+    pub fn record_write(&mut self, write: WriteRecord) {
+        let Some(next) = self.replay else {
+            self.writes.push_back(write);
+            return;
+        };
+
+        if self.writes.get(next) != Some(&write) && self.diverged.is_none() {
+            self.diverged = Some(write.cycle);
+        }
+        self.replay = Some(next + 1);
+    }
+
+    fn snapshot_due(&self, cycles: u64) -> bool {
+        match self.snapshots.back() {
+            Some(&(cycle, _)) => cycles >= cycle + self.policy.snapshot_interval,
+            None => true,
+        }
+    }
+
+    // Keeps the newest snapshot that still covers the whole history and everything after it
+    fn push_snapshot(&mut self, cycles: u64, state: Vec<u8>) {
+        self.snapshots.push_back((cycles, state));
+        while self.snapshots.len() > 1 && cycles - self.snapshots[1].0 >= self.policy.history {
+            self.snapshots.pop_front();
+        }
+
+        let oldest = self.snapshots[0].0;
+        while self
+            .instructions
+            .front()
+            .is_some_and(|&(cycle, _)| cycle < oldest)
+        {
+            self.instructions.pop_front();
+        }
+        while self
+            .writes
+            .front()
+            .is_some_and(|write| write.cycle < oldest)
+        {
+            self.writes.pop_front();
+        }
+    }
+
+    // Forgets everything from the given cycle on, it is about to be run again
+    fn truncate(&mut self, cycles: u64) {
+        self.snapshots.retain(|&(cycle, _)| cycle <= cycles);
+        self.instructions.retain(|&(cycle, _)| cycle < cycles);
+        self.writes.retain(|write| write.cycle < cycles);
+    }
+
+    fn clear(&mut self) {
+        self.snapshots.clear();
+        self.instructions.clear();
+        self.writes.clear();
+    }
+}
+
+impl<B: AddressBus> MOS6502<B> {
+    // Starts keeping the history needed to rewind (or stops with None). Snapshots are save
+    // states so the bus has to support them for rewinding to restore memory
+    pub fn set_rewind(&mut self, policy: Option<RewindPolicy>) {
+        self.rewind = policy.map(Rewind::new);
+        self.restart_rewind();
+    }
+
+    // Drops the history, it starts over from the current state
+    pub(super) fn restart_rewind(&mut self) {
+        if self.rewind.is_none() {
+            return;
+        }
+
+        let state = self.save_state();
+        let rewind = self.rewind.as_mut().unwrap();
+        rewind.clear();
+        rewind.push_snapshot(self.cycles, state);
+    }
+
+    // Called before every cycle
+    pub(super) fn record_rewind(&mut self) {
+        let rewind = self.rewind.as_mut().unwrap();
+        if rewind.replay.is_some() {
+            return;
+        }
+
+        if self.sequence.is_none() {
+            rewind.instructions.push_back((self.cycles, self.reg.pc));
+        }
+        if rewind.snapshot_due(self.cycles) {
+            let state = self.save_state();
+            self.rewind
+                .as_mut()
+                .unwrap()
+                .push_snapshot(self.cycles, state);
+        }
+    }
+
+    // The journaled writes, oldest first
+    pub fn rewind_writes(&self) -> impl Iterator<Item = &WriteRecord> {
+        self.rewind.iter().flat_map(|rewind| rewind.writes.iter())
+    }
+
+    // Goes back to the state the CPU was in when cycles() was at the given cycle, which can be
+    // in the middle of an instruction. Nothing changes when it fails
+    pub fn rewind_to(&mut self, cycle: u64) -> Result<(), RewindError> {
+        let Some(rewind) = &self.rewind else {
+            return Err(RewindError::Disabled);
+        };
+        if cycle > self.cycles {
+            return Err(RewindError::OutOfHistory);
+        }
+        let Some(index) = rewind
+            .snapshots
+            .iter()
+            .rposition(|&(start, _)| start <= cycle)
+        else {
+            return Err(RewindError::OutOfHistory);
+        };
+
+        // Taken out so loading the snapshot doesn't restart the history
+        let mut rewind = self.rewind.take().unwrap();
+        let backup = self.save_state();
+        let (start, snapshot) = &rewind.snapshots[index];
+        if let Err(error) = self.load_state(snapshot) {
+            self.rewind = Some(rewind);
+            return Err(RewindError::State(error));
+        }
+        let start = *start;
+        let expected_writes = rewind.writes.partition_point(|write| write.cycle < cycle);
+        rewind.replay = Some(rewind.writes.partition_point(|write| write.cycle < start));
+        self.rewind = Some(rewind);
+
+        // Replays as fast as possible, without logging or tracking the accesses a second time
+        let bus_log = self.bus_log.take();
+        let provenance = self.provenance.take();
+        let clock = std::mem::take(&mut self.clock);
+        while self.cycles < cycle && self.rewind.as_ref().unwrap().diverged.is_none() {
+            // Errors were already reported when these instructions first ran
+            let _ = self.tick_cycle();
+        }
+        self.bus_log = bus_log;
+        self.provenance = provenance;
+        self.clock = clock;
+        self.clock.resync();
+
+        let mut rewind = self.rewind.take().unwrap();
+        let replayed = rewind.replay.take().unwrap();
+        if replayed != expected_writes && rewind.diverged.is_none() {
+            let missing = rewind.writes.get(replayed.min(expected_writes));
+            rewind.diverged = Some(missing.map_or(cycle, |write| write.cycle));
+        }
+        if let Some(diverged) = rewind.diverged.take() {
+            self.load_state(&backup)
+                .expect("A state just saved always loads");
+            self.rewind = Some(rewind);
+            return Err(RewindError::Diverged { cycle: diverged });
+        }
+
+        rewind.truncate(cycle);
+        self.rewind = Some(rewind);
+        if let Some(provenance) = &mut self.provenance {
+            provenance.truncate(cycle);
+        }
+        Ok(())
+    }
+
+    pub fn rewind_cycles(&mut self, cycles: u64) -> Result<(), RewindError> {
+        let Some(cycle) = self.cycles.checked_sub(cycles) else {
+            return Err(RewindError::OutOfHistory);
+        };
+        self.rewind_to(cycle)
+    }
+
+    // Back to the start of the previous instruction, or of the current one when tick_cycle()
+    // left it unfinished
+    pub fn step_back(&mut self) -> Result<(), RewindError> {
+        let cycle = self.find_instruction(|_| true)?;
+        self.rewind_to(cycle)
+    }
+
+    // Back to the last time an instruction at the given address was about to run
+    pub fn run_back_to(&mut self, address: u16) -> Result<(), RewindError> {
+        let cycle = self.find_instruction(|pc| pc == address)?;
+        self.rewind_to(cycle)
+    }
+
+    // Back to the start of the instruction that last wrote to the given address, the write it
+    // did is returned
+    pub fn run_back_to_write(&mut self, address: u16) -> Result<WriteRecord, RewindError> {
+        let rewind = self.rewind.as_ref().ok_or(RewindError::Disabled)?;
+        let write = *rewind
+            .writes
+            .iter()
+            .rev()
+            .find(|write| write.address == address && write.cycle < self.cycles)
+            .ok_or(RewindError::NotFound)?;
+
+        let index = rewind
+            .instructions
+            .partition_point(|&(cycle, _)| cycle <= write.cycle);
+        let Some(&(cycle, _)) = index.checked_sub(1).map(|i| &rewind.instructions[i]) else {
+            return Err(RewindError::OutOfHistory);
+        };
+        self.rewind_to(cycle)?;
+        Ok(write)
+    }
+
+    // The cycle the last instruction before now matching the predicate began at
+    fn find_instruction(&self, matches: impl Fn(u16) -> bool) -> Result<u64, RewindError> {
+        let rewind = self.rewind.as_ref().ok_or(RewindError::Disabled)?;
+        rewind
+            .instructions
+            .iter()
+            .rev()
+            .find(|&&(cycle, pc)| cycle < self.cycles && matches(pc))
+            .map(|&(cycle, _)| cycle)
+            .ok_or(RewindError::NotFound)
+    }
+}
@@ -1,48 +1,99 @@
+use crate::cpu::opcode_modes::implied_read;
 use crate::cpu::*;
 
-pub fn transfer_sp_to_x(cpu: &mut MOS6502) {
+pub fn transfer_sp_to_x<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.ix = cpu.reg.sp;
     cpu.set_zn(cpu.reg.ix);
-    cpu.tick();
 }
 
-pub fn transfer_x_to_sp(cpu: &mut MOS6502) {
+pub fn transfer_x_to_sp<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.sp = cpu.reg.ix;
-    cpu.tick();
 }
 
-pub fn push_ac(cpu: &mut MOS6502) {
-    // T1
-    cpu.stack_push(cpu.reg.ac);
-    cpu.tick();
+fn push<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) -> CycleResult {
+    match cpu.instruction_cycle {
+        // T1
+        1 => implied_read(cpu),
+        // T2
+        _ => {
+            cpu.stack_push(value);
+            cpu.tick();
+            Ok(Cycle::Done)
+        }
+    }
 }
 
-pub fn pull_ac(cpu: &mut MOS6502) {
-    // T1
-    cpu.stack_peek();
-    cpu.stack_pop_no_read();
-    cpu.tick();
+// Returns the pulled value on the last cycle
+fn pull<B: AddressBus>(cpu: &mut MOS6502<B>) -> Result<Option<u8>, Halted> {
+    match cpu.instruction_cycle {
+        // T1
+        1 => {
+            implied_read(cpu)?;
+        }
+        // T2
+        2 => {
+            cpu.stack_peek()?;
+            cpu.stack_pop_no_read();
+            cpu.tick();
+        }
+        // T3
+        _ => return Ok(Some(cpu.stack_peek()?)),
+    }
+    Ok(None)
+}
 
-    // T2
-    cpu.reg.ac = cpu.stack_peek();
+pub fn push_ac<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    push(cpu, cpu.reg.ac)
+}
+
+pub fn pull_ac<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let Some(value) = pull(cpu)? else {
+        return Ok(Cycle::Next);
+    };
+    cpu.reg.ac = value;
     cpu.set_zn(cpu.reg.ac);
     cpu.tick();
+    Ok(Cycle::Done)
+}
+
+pub fn push_processor<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let flags = cpu.reg.ps | CPUFLAGS::UNUSED | CPUFLAGS::BREAK;
+    push(cpu, flags.bits())
 }
 
-pub fn push_processor(cpu: &mut MOS6502) {
-    // T1
-    cpu.push_processor_status();
+pub fn pull_processor_status<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let Some(value) = pull(cpu)? else {
+        return Ok(Cycle::Next);
+    };
+    cpu.set_proccessor_status(value);
     cpu.tick();
+    Ok(Cycle::Done)
+}
+
+pub fn push_ix<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    push(cpu, cpu.reg.ix)
+}
+
+pub fn push_iy<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    push(cpu, cpu.reg.iy)
 }
 
-pub fn pull_processor_status(cpu: &mut MOS6502) {
-    // T1
-    cpu.stack_peek();
-    cpu.stack_pop_no_read();
+pub fn pull_ix<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let Some(value) = pull(cpu)? else {
+        return Ok(Cycle::Next);
+    };
+    cpu.reg.ix = value;
+    cpu.set_zn(cpu.reg.ix);
     cpu.tick();
+    Ok(Cycle::Done)
+}
 
-    // T2
-    let value = cpu.stack_peek();
-    cpu.set_proccessor_status(value);
+pub fn pull_iy<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    let Some(value) = pull(cpu)? else {
+        return Ok(Cycle::Next);
+    };
+    cpu.reg.iy = value;
+    cpu.set_zn(cpu.reg.iy);
     cpu.tick();
+    Ok(Cycle::Done)
 }
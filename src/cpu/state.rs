@@ -0,0 +1,293 @@
+use crate::cpu::*;
+use crate::save_state::{StateError, StateReader, StateWriter};
+
+// Version 1 fields, in order:
+//
+//   variant             u8    0 NMOS 6502, 1 Ricoh 2A03, 2 65SC02, 3 65C02, 4 W65C02S
+//   pc                  u16
+//   sp, a, x, y, p      u8 each
+//   cycles              u64
+//   instruction address u16
+//   trap                u8 kind (0 none, 1 self jump, 2 self branch, 3 BRK, 4 JAM, 5 STP,
+//                       6 exit write, 7 PC, 8 cycle budget), u16 address, u8 value, u64 cycles
+//   jammed, waiting, stopped, undocumented opcodes   bool each
+//   magic constant      u8
+//   IRQ line, NMI line, NMI detected, interrupt sampled, interrupt pending, SO line,
+//   SO detected         bool each
+//   scheduled lines     u32 count, then u64 cycle, u8 line (0 IRQ, 1 NMI, 2 SO), bool asserted
+//   stall cycles        u64
+//   halted at           bool present, u64 cycle
+//   halted cycles       u64
+//   halted              bool
+//   sequence            u8 kind (0 between instructions, 1 fetch, 2 opcode, 3 interrupt,
+//                       4 jammed, 5 stopped, 6 waiting), u8 opcode or interrupt (0 NMI, 1 IRQ,
+//                       2 BRK, 3 reset)
+//   instruction cycle   u8
+//   sequence start      u64
+//   address, base address, pointer   u16 each
+//   data                u8
+//   decimal fixup, interrupt delayed   bool each
+//
+// Bools are a byte holding 0 or 1. The bus log, clock and trap policy aren't saved
+
+impl<B: AddressBus> MOS6502<B> {
+    // Everything needed to resume the CPU and its bus later, even in the middle of an
+    // instruction run with tick_cycle()
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.u8(match self.variant {
+            CpuVariant::Nmos6502 => 0,
+            CpuVariant::Ricoh2A03 => 1,
+            CpuVariant::Cmos65SC02 => 2,
+            CpuVariant::Cmos65C02 => 3,
+            CpuVariant::W65C02S => 4,
+        });
+        writer.u16(self.reg.pc);
+        writer.u8(self.reg.sp);
+        writer.u8(self.reg.ac);
+        writer.u8(self.reg.ix);
+        writer.u8(self.reg.iy);
+        writer.u8(self.reg.ps.bits());
+        writer.u64(self.cycles);
+        writer.u16(self.instruction_address);
+        write_trap(&mut writer, self.trap);
+        writer.bool(self.jammed);
+        writer.bool(self.waiting);
+        writer.bool(self.stopped);
+        writer.bool(self.undocumented_opcodes);
+        writer.u8(self.magic_constant);
+
+        writer.bool(self.irq_line);
+        writer.bool(self.nmi_line);
+        writer.bool(self.nmi_detected);
+        writer.bool(self.interrupt_sampled);
+        writer.bool(self.interrupt_pending);
+        writer.bool(self.so_line);
+        writer.bool(self.so_detected);
+        writer.u32(self.scheduled_lines.len() as u32);
+        for &(cycle, line, asserted) in &self.scheduled_lines {
+            writer.u64(cycle);
+            writer.u8(match line {
+                InputLine::Irq => 0,
+                InputLine::Nmi => 1,
+                InputLine::So => 2,
+            });
+            writer.bool(asserted);
+        }
+
+        writer.u64(self.stall_cycles);
+        writer.bool(self.halted_at.is_some());
+        writer.u64(self.halted_at.unwrap_or(0));
+        writer.u64(self.halted_cycles);
+        writer.bool(self.halted);
+
+        let (kind, operand) = match self.sequence {
+            None => (0, 0),
+            Some(Sequence::Fetch) => (1, 0),
+            Some(Sequence::Opcode(opcode)) => (2, opcode),
+            Some(Sequence::Interrupt(interrupt)) => (3, interrupt_id(interrupt)),
+            Some(Sequence::Jammed) => (4, 0),
+            Some(Sequence::Stopped) => (5, 0),
+            Some(Sequence::Waiting) => (6, 0),
+        };
+        writer.u8(kind);
+        writer.u8(operand);
+        writer.u8(self.instruction_cycle);
+        writer.u64(self.sequence_start);
+        writer.u16(self.address);
+        writer.u16(self.base_address);
+        writer.u16(self.pointer);
+        writer.u8(self.data);
+        writer.bool(self.decimal_fixup);
+        writer.bool(self.interrupt_delayed);
+
+        let mut bus = Vec::new();
+        self.bus.save_state(&mut bus);
+        writer.u32(bus.len() as u32);
+        writer.bytes(&bus);
+        writer.into_bytes()
+    }
+
+    // Restores a state from save_state(), leaving the CPU and bus as they were when it fails
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let backup = self.save_state();
+        let result = self.read_state(state);
+        match result {
+            Ok(()) => self.restart_rewind(),
+            Err(_) => self
+                .read_state(&backup)
+                .expect("A state just saved always loads"),
+        }
+        self.clock.resync();
+        result
+    }
+
+    // Each format version has its own reader for the CPU fields, the bus state is the same
+    fn read_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(state)?;
+        match reader.version() {
+            1 => self.read_state_v1(&mut reader)?,
+            version => return Err(StateError::UnsupportedVersion(version)),
+        }
+
+        let length = reader.u32()? as usize;
+        let bus = reader.bytes(length)?;
+        reader.finish()?;
+        self.bus.load_state(bus)
+    }
+
+    fn read_state_v1(&mut self, reader: &mut StateReader) -> Result<(), StateError> {
+        self.variant = match reader.u8()? {
+            0 => CpuVariant::Nmos6502,
+            1 => CpuVariant::Ricoh2A03,
+            2 => CpuVariant::Cmos65SC02,
+            3 => CpuVariant::Cmos65C02,
+            4 => CpuVariant::W65C02S,
+            _ => return Err(StateError::Invalid("variant")),
+        };
+        self.opcodes = opcode_table::opcode_table(self.variant);
+        self.reg.pc = reader.u16()?;
+        self.reg.sp = reader.u8()?;
+        self.reg.ac = reader.u8()?;
+        self.reg.ix = reader.u8()?;
+        self.reg.iy = reader.u8()?;
+        self.reg.ps = CPUFLAGS::from_bits_retain(reader.u8()?);
+        self.cycles = reader.u64()?;
+        self.instruction_address = reader.u16()?;
+        self.trap = read_trap(reader)?;
+        self.jammed = reader.bool()?;
+        self.waiting = reader.bool()?;
+        self.stopped = reader.bool()?;
+        self.undocumented_opcodes = reader.bool()?;
+        self.magic_constant = reader.u8()?;
+
+        self.irq_line = reader.bool()?;
+        self.nmi_line = reader.bool()?;
+        self.nmi_detected = reader.bool()?;
+        self.interrupt_sampled = reader.bool()?;
+        self.interrupt_pending = reader.bool()?;
+        self.so_line = reader.bool()?;
+        self.so_detected = reader.bool()?;
+        self.scheduled_lines.clear();
+        for _ in 0..reader.u32()? {
+            let cycle = reader.u64()?;
+            let line = match reader.u8()? {
+                0 => InputLine::Irq,
+                1 => InputLine::Nmi,
+                2 => InputLine::So,
+                _ => return Err(StateError::Invalid("scheduled line")),
+            };
+            self.scheduled_lines.push((cycle, line, reader.bool()?));
+        }
+
+        self.stall_cycles = reader.u64()?;
+        let halted = reader.bool()?;
+        let halted_at = reader.u64()?;
+        self.halted_at = halted.then_some(halted_at);
+        self.halted_cycles = reader.u64()?;
+        self.halted = reader.bool()?;
+
+        let kind = reader.u8()?;
+        let operand = reader.u8()?;
+        self.sequence = match kind {
+            0 => None,
+            1 => Some(Sequence::Fetch),
+            2 => Some(Sequence::Opcode(operand)),
+            3 => Some(Sequence::Interrupt(read_interrupt(operand)?)),
+            4 => Some(Sequence::Jammed),
+            5 => Some(Sequence::Stopped),
+            6 => Some(Sequence::Waiting),
+            _ => return Err(StateError::Invalid("sequence")),
+        };
+        self.instruction_cycle = reader.u8()?;
+        if !self.instruction_cycle_fits() {
+            return Err(StateError::Invalid("instruction cycle"));
+        }
+        self.sequence_start = reader.u64()?;
+        self.address = reader.u16()?;
+        self.base_address = reader.u16()?;
+        self.pointer = reader.u16()?;
+        self.data = reader.u8()?;
+        self.decimal_fixup = reader.bool()?;
+        self.interrupt_delayed = reader.bool()?;
+        Ok(())
+    }
+
+    // The handlers index their cycles from the sequence start, one outside of the sequence
+    // would run the wrong bus cycles or underflow. An opcode resumes after its fetch (cycle 0)
+    // and can run at most its base count plus a page crossing and a taken branch or decimal
+    // fixup, a JAM runs T1 to T4 before it locks up
+    fn instruction_cycle_fits(&self) -> bool {
+        let last = match self.sequence {
+            None
+            | Some(Sequence::Fetch | Sequence::Jammed | Sequence::Stopped | Sequence::Waiting) => 0,
+            Some(Sequence::Opcode(opcode)) => match self.opcodes[opcode as usize].cycles {
+                0 => 4,
+                cycles => cycles + 1,
+            },
+            Some(Sequence::Interrupt(InterruptType::Reset)) => 4,
+            Some(Sequence::Interrupt(_)) => 6,
+        };
+        let first = match self.sequence {
+            Some(Sequence::Opcode(_)) => 1,
+            _ => 0,
+        };
+        (first..=last).contains(&self.instruction_cycle)
+    }
+}
+
+fn interrupt_id(interrupt: InterruptType) -> u8 {
+    match interrupt {
+        InterruptType::Nmi => 0,
+        InterruptType::Irq => 1,
+        InterruptType::Brk => 2,
+        InterruptType::Reset => 3,
+    }
+}
+
+fn read_interrupt(id: u8) -> Result<InterruptType, StateError> {
+    match id {
+        0 => Ok(InterruptType::Nmi),
+        1 => Ok(InterruptType::Irq),
+        2 => Ok(InterruptType::Brk),
+        3 => Ok(InterruptType::Reset),
+        _ => Err(StateError::Invalid("interrupt")),
+    }
+}
+
+fn write_trap(writer: &mut StateWriter, trap: Option<Trap>) {
+    let (kind, address, value, cycles) = match trap {
+        None => (0, 0, 0, 0),
+        Some(Trap::SelfJump { address }) => (1, address, 0, 0),
+        Some(Trap::SelfBranch { address }) => (2, address, 0, 0),
+        Some(Trap::Brk { address }) => (3, address, 0, 0),
+        Some(Trap::Jam { address }) => (4, address, 0, 0),
+        Some(Trap::Stop { address }) => (5, address, 0, 0),
+        Some(Trap::ExitWrite { address, value }) => (6, address, value, 0),
+        Some(Trap::Pc { address }) => (7, address, 0, 0),
+        Some(Trap::CycleBudget { address, cycles }) => (8, address, 0, cycles),
+    };
+    writer.u8(kind);
+    writer.u16(address);
+    writer.u8(value);
+    writer.u64(cycles);
+}
+
+fn read_trap(reader: &mut StateReader) -> Result<Option<Trap>, StateError> {
+    let kind = reader.u8()?;
+    let address = reader.u16()?;
+    let value = reader.u8()?;
+    let cycles = reader.u64()?;
+    Ok(Some(match kind {
+        0 => return Ok(None),
+        1 => Trap::SelfJump { address },
+        2 => Trap::SelfBranch { address },
+        3 => Trap::Brk { address },
+        4 => Trap::Jam { address },
+        5 => Trap::Stop { address },
+        6 => Trap::ExitWrite { address, value },
+        7 => Trap::Pc { address },
+        8 => Trap::CycleBudget { address, cycles },
+        _ => return Err(StateError::Invalid("trap")),
+    }))
+}
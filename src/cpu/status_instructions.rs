@@ -1,29 +1,53 @@
 use crate::cpu::*;
 
-pub fn clear_carry(cpu: &mut MOS6502) {
+pub fn clear_carry<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::CARRY, false);
 }
 
-pub fn clear_decimal(cpu: &mut MOS6502) {
+pub fn clear_decimal<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::DECIMAL, false);
 }
 
-pub fn clear_int_disable(cpu: &mut MOS6502) {
+pub fn clear_int_disable<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::INT_DISABLE, false);
 }
 
-pub fn clear_overflow(cpu: &mut MOS6502) {
+pub fn clear_overflow<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::OVERFLOW, false);
 }
 
-pub fn set_carry(cpu: &mut MOS6502) {
+pub fn set_carry<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::CARRY, true);
 }
 
-pub fn set_decimal(cpu: &mut MOS6502) {
+pub fn set_decimal<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::DECIMAL, true);
 }
 
-pub fn set_int_disable(cpu: &mut MOS6502) {
+pub fn set_int_disable<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.set(CPUFLAGS::INT_DISABLE, true);
 }
+
+pub fn wait_for_interrupt<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    // T1, T2
+    cpu.read(cpu.reg.pc)?;
+    if cpu.instruction_cycle == 1 {
+        cpu.tick();
+        return Ok(Cycle::Next);
+    }
+    cpu.waiting();
+    cpu.tick();
+    Ok(Cycle::Done)
+}
+
+pub fn stop<B: AddressBus>(cpu: &mut MOS6502<B>) -> CycleResult {
+    // T1, T2
+    cpu.read(cpu.reg.pc)?;
+    if cpu.instruction_cycle == 1 {
+        cpu.tick();
+        return Ok(Cycle::Next);
+    }
+    cpu.stopped();
+    cpu.tick();
+    Ok(Cycle::Done)
+}
@@ -1,48 +1,52 @@
 use crate::cpu::*;
 
-pub fn load_ac(cpu: &mut MOS6502, value: u8) {
+pub fn load_ac<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     cpu.set_zn(value);
     cpu.reg.ac = value;
 }
 
-pub fn load_ix(cpu: &mut MOS6502, value: u8) {
+pub fn load_ix<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     cpu.set_zn(value);
     cpu.reg.ix = value;
 }
 
-pub fn load_iy(cpu: &mut MOS6502, value: u8) {
+pub fn load_iy<B: AddressBus>(cpu: &mut MOS6502<B>, value: u8) {
     cpu.set_zn(value);
     cpu.reg.iy = value;
 }
 
-pub fn store_ac(cpu: &mut MOS6502) -> u8 {
+pub fn store_ac<B: AddressBus>(cpu: &mut MOS6502<B>) -> u8 {
     cpu.reg.ac
 }
 
-pub fn store_ix(cpu: &mut MOS6502) -> u8 {
+pub fn store_ix<B: AddressBus>(cpu: &mut MOS6502<B>) -> u8 {
     cpu.reg.ix
 }
 
-pub fn store_iy(cpu: &mut MOS6502) -> u8 {
+pub fn store_iy<B: AddressBus>(cpu: &mut MOS6502<B>) -> u8 {
     cpu.reg.iy
 }
 
-pub fn transfer_ac_to_x(cpu: &mut MOS6502) {
+pub fn store_zero<B: AddressBus>(_cpu: &mut MOS6502<B>) -> u8 {
+    0
+}
+
+pub fn transfer_ac_to_x<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.ix = cpu.reg.ac;
     cpu.set_zn(cpu.reg.ac);
 }
 
-pub fn transfer_ac_to_y(cpu: &mut MOS6502) {
+pub fn transfer_ac_to_y<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.iy = cpu.reg.ac;
     cpu.set_zn(cpu.reg.ac);
 }
 
-pub fn transfer_x_to_ac(cpu: &mut MOS6502) {
+pub fn transfer_x_to_ac<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.ac = cpu.reg.ix;
     cpu.set_zn(cpu.reg.ac);
 }
 
-pub fn transfer_y_to_ac(cpu: &mut MOS6502) {
+pub fn transfer_y_to_ac<B: AddressBus>(cpu: &mut MOS6502<B>) {
     cpu.reg.ac = cpu.reg.iy;
     cpu.set_zn(cpu.reg.ac);
 }
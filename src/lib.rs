@@ -0,0 +1,8 @@
+pub mod address_bus;
+pub mod clock;
+pub mod cpu;
+pub mod disassembler;
+pub mod save_state;
+mod tests;
+pub mod trace_diff;
+pub mod tracer;
@@ -1,8 +1,74 @@
-#![feature(unchecked_math)]
-#![feature(bigint_helper_methods)]
-mod address_bus;
-mod cpu;
-mod disassembler;
-mod tests;
-
-fn main() {}
+use mos_6502::trace_diff;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mos_6502 trace-diff [--context LINES] [--relative-cycles] LEFT RIGHT
+
+Compares two instruction traces and prints the first line where PC, registers, flags or
+cycles differ. Exits with 0 when they match, 1 when they differ and 2 on errors";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("trace-diff") => trace_diff_command(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn trace_diff_command(args: &[String]) -> ExitCode {
+    let mut options = trace_diff::DiffOptions::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--relative-cycles" => options.relative_cycles = true,
+            "--context" => match args.next().and_then(|lines| lines.parse().ok()) {
+                Some(lines) => options.context = lines,
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            flag if flag.starts_with('-') => {
+                eprintln!("Unknown option {}\n\n{}", flag, USAGE);
+                return ExitCode::from(2);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [left, right] = paths[..] else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let open = |path: &String| match File::open(path) {
+        Ok(file) => Some(BufReader::new(file)),
+        Err(error) => {
+            eprintln!("Can't open {}: {}", path, error);
+            None
+        }
+    };
+    let (Some(left_file), Some(right_file)) = (open(left), open(right)) else {
+        return ExitCode::from(2);
+    };
+
+    match trace_diff::diff_traces(left_file, right_file, &options) {
+        Ok(None) => {
+            println!("Traces match");
+            ExitCode::SUCCESS
+        }
+        Ok(Some(divergence)) => {
+            print!("{}", divergence);
+            ExitCode::from(1)
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+// Save states are little endian and laid out as
+//
+//   magic    4 bytes  "6502"
+//   version  u16      The format version it was written with
+//   cpu      ...      The fields of that version, see cpu/state.rs
+//   length   u32      Size of the bus state
+//   bus      length   Whatever the bus wrote in AddressBus::save_state()
+//
+// Any change to the layout bumps FORMAT_VERSION and keeps reading the older versions so
+// existing files stay loadable
+pub const MAGIC: &[u8; 4] = b"6502";
+pub const FORMAT_VERSION: u16 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StateError {
+    NotASaveState,           // Doesn't start with the magic
+    UnsupportedVersion(u16), // Written by a newer release
+    Truncated,
+    Invalid(&'static str), // A field holds a value that is never written
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::NotASaveState => write!(f, "Not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {}", version)
+            }
+            StateError::Truncated => write!(f, "Save state is truncated"),
+            StateError::Invalid(field) => write!(f, "Invalid {} in save state", field),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+pub struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateWriter {
+    // Starts with the header of the current version
+    pub fn new() -> Self {
+        let mut writer = Self { bytes: Vec::new() };
+        writer.bytes(MAGIC);
+        writer.u16(FORMAT_VERSION);
+        writer
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    version: u16,
+}
+
+impl<'a> StateReader<'a> {
+    // Checks the header, the fields are read with the methods below
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        let mut reader = Self { data, version: 0 };
+        if reader.bytes(MAGIC.len()) != Ok(MAGIC) {
+            return Err(StateError::NotASaveState);
+        }
+
+        reader.version = reader.u16()?;
+        if reader.version == 0 || reader.version > FORMAT_VERSION {
+            return Err(StateError::UnsupportedVersion(reader.version));
+        }
+        Ok(reader)
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    // Fails if anything is left after the last field
+    pub fn finish(&self) -> Result<(), StateError> {
+        if !self.data.is_empty() {
+            return Err(StateError::Invalid("trailing data"));
+        }
+        Ok(())
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < length {
+            return Err(StateError::Truncated);
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Invalid("flag")),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
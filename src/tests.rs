@@ -1,2 +1,47 @@
+mod address_wrap_test;
 mod addressing_mode_test;
+mod benchmark_test;
+mod breakpoints_test;
+mod bus_log_test;
+mod clock_test;
+mod cmos_65c02_test;
+mod cycle_timing_test;
+mod decimal_mode_test;
+mod disassembler_test;
 mod functional_6502_test;
+mod illegal_opcodes_test;
+mod interrupt_6502_test;
+mod interrupt_test;
+mod jmp_indirect_test;
+mod provenance_test;
+mod rdy_test;
+mod rewind_test;
+mod save_state_test;
+mod set_overflow_test;
+mod single_step_test;
+mod tick_cycle_test;
+mod trace_diff_test;
+mod tracer_test;
+mod trap_test;
+
+#[cfg(test)]
+use crate::address_bus::{AddressBus, MemoryBank};
+#[cfg(test)]
+use crate::cpu::{CpuVariant, MOS6502};
+
+// Writes the program to memory from start on and points the PC at its first byte
+#[cfg(test)]
+pub fn load_program<B: AddressBus>(cpu: &mut MOS6502<B>, start: u16, program: &[u8]) {
+    for (i, byte) in program.iter().enumerate() {
+        cpu.bus.write(start.wrapping_add(i as u16), *byte);
+    }
+    cpu.set_pc(start);
+}
+
+// A CPU of the variant on its own memory with the program loaded at start
+#[cfg(test)]
+pub fn cpu_with_program(variant: CpuVariant, start: u16, program: &[u8]) -> MOS6502<MemoryBank> {
+    let mut cpu = MOS6502::with_variant(MemoryBank::new(), variant);
+    load_program(&mut cpu, start, program);
+    cpu
+}
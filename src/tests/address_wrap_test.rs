@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{CpuVariant, CPUFLAGS};
+    use crate::tests::cpu_with_program;
+
+    #[test]
+    fn operand_wrap_test() {
+        // LDA #$42 at $FFFF, the operand is at $0000
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFF, &[0xA9, 0x42]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x42);
+        assert_eq!(cpu.reg.pc, 0x0001);
+
+        // LDA $1234 at $FFFE, the high byte is at $0000
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFE, &[0xAD, 0x34, 0x12]);
+        cpu.bus.write(0x1234, 0x56);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x56);
+        assert_eq!(cpu.reg.pc, 0x0001);
+
+        // JMP $1234 at $FFFD
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFD, &[0x4C, 0x34, 0x12]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x1234);
+
+        // JMP ($1234) at $FFFE, on the 65C02 its extra cycle reads the operand at $0000 again
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, 0xFFFE, &[0x6C, 0x34, 0x12]);
+        cpu.bus.write(0x1234, 0x78);
+        cpu.bus.write(0x1235, 0x56);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x5678);
+    }
+
+    #[test]
+    fn branch_wrap_test() {
+        // BNE *+$4 at $FFFE, taken across the end of memory
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFE, &[0xD0, 0x02]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x0002);
+
+        // BEQ *-$2 at $0000 is not taken
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0x0000, &[0xF0, 0xFC]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x0002);
+
+        // BNE *-$2 at $0000 goes back to $FFFE
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0x0000, &[0xD0, 0xFC]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0xFFFE);
+    }
+
+    #[test]
+    fn return_wrap_test() {
+        // JSR $FFFF at $0200; the RTS at $FFFF returns to $0203
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0x0200, &[0x20, 0xFF, 0xFF]);
+        cpu.bus.write(0xFFFF, 0x60);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x0203);
+
+        // RTS with $FFFF on the stack continues at $0000
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0x0200, &[0x60]);
+        cpu.reg.sp = 0xFD;
+        cpu.bus.write(0x01FE, 0xFF);
+        cpu.bus.write(0x01FF, 0xFF);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x0000);
+    }
+
+    #[test]
+    fn indexed_wrap_test() {
+        // LDA $FFFF,X at $FFFD with X = 1 reads $0000
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFD, &[0xBD, 0xFF, 0xFF]);
+        cpu.bus.write(0x0000, 0x42);
+        cpu.reg.ix = 0x01;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x42);
+        assert_eq!(cpu.reg.pc, 0x0000);
+
+        // LDA ($10),Y at $FFFF, the pointer is at $0000 and $10 holds $FFFF
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0xFFFF, &[0xB1, 0x10]);
+        cpu.bus.write(0x0010, 0xFF);
+        cpu.bus.write(0x0011, 0xFF);
+        cpu.bus.write(0x0001, 0x42);
+        cpu.reg.iy = 0x02;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x42);
+        assert_eq!(cpu.reg.pc, 0x0001);
+    }
+
+    // Every opcode, with its operands running past $FFFF, returns from step() instead of
+    // panicking
+    #[test]
+    fn every_addressing_mode_wrap_test() {
+        for variant in [
+            CpuVariant::Nmos6502,
+            CpuVariant::Ricoh2A03,
+            CpuVariant::Cmos65SC02,
+            CpuVariant::Cmos65C02,
+            CpuVariant::W65C02S,
+        ] {
+            for start in [0xFFFD, 0xFFFE, 0xFFFF] {
+                for opcode in 0..=0xFF_u8 {
+                    let mut cpu = cpu_with_program(variant, start, &[opcode]);
+                    for address in [0xFFFE, 0xFFFF, 0x0000, 0x0001] {
+                        if address != start {
+                            cpu.bus.write(address, 0xFF);
+                        }
+                    }
+                    cpu.reg.ix = 0xFF;
+                    cpu.reg.iy = 0xFF;
+                    cpu.reg.ps.remove(CPUFLAGS::ZERO);
+                    let _ = cpu.step();
+                }
+            }
+        }
+    }
+}
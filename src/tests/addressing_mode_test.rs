@@ -3,7 +3,10 @@ use crate::cpu::*;
 
 #[cfg(test)]
 mod tests {
+    use crate::address_bus::MemoryBank;
     use crate::cpu::opcode_modes::*;
+    use crate::cpu::opcode_table::opcode_table;
+    use crate::cpu::CpuVariant;
     use std::collections::HashMap;
 
     #[test]
@@ -162,14 +165,254 @@ mod tests {
         op_codes_to_addressing_mode.insert(0x9A, AddressingMode::Implied);
         op_codes_to_addressing_mode.insert(0x98, AddressingMode::Implied);
 
+        // Undocumented (NMOS)
+        op_codes_to_addressing_mode.insert(0x4B, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x0B, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x2B, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x6B, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xC7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xD7, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xCF, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0xDF, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0xDB, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0xC3, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0xD3, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0xE7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xF7, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xEF, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0xFF, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0xFB, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0xE3, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0xF3, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x02, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x12, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x22, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x32, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x42, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x52, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x62, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x72, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x92, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xB2, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xD2, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xF2, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xBB, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0xA7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xB7, AddressingMode::ZeroPageY);
+        op_codes_to_addressing_mode.insert(0xAF, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0xBF, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0xA3, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0xB3, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0xAB, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x1A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x3A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x5A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x7A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xDA, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xFA, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x80, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x82, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x89, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xC2, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xE2, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x04, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x44, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x64, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x14, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x34, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x54, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x74, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xD4, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xF4, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x0C, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x1C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x3C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x5C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x7C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0xDC, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0xFC, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x27, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x37, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x2F, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x3F, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x3B, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x23, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0x33, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x67, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x77, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x6F, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x7F, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x7B, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x63, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0x73, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x87, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x97, AddressingMode::ZeroPageY);
+        op_codes_to_addressing_mode.insert(0x8F, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x83, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0xEB, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xCB, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x93, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x9F, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x9E, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x9C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x07, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x17, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x0F, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x1F, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x1B, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x03, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0x13, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x47, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x57, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x4F, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x5F, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x5B, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x43, AddressingMode::IndirectX);
+        op_codes_to_addressing_mode.insert(0x53, AddressingMode::IndirectY);
+        op_codes_to_addressing_mode.insert(0x9B, AddressingMode::AbsoluteY);
+        op_codes_to_addressing_mode.insert(0x8B, AddressingMode::Immediate);
+
+        assert_eq!(op_codes_to_addressing_mode.len(), 256);
+
+        let mut failed = false;
         for (key, value) in op_codes_to_addressing_mode.into_iter() {
-            let got = get_addressing_mode(key);
+            let got = opcode_table::<MemoryBank>(CpuVariant::Nmos6502)[key as usize].mode;
             if got != value {
                 println!(
                     "Invalid addressing mode for {:x} Expected: {:?}, Got: {:?}",
                     key, value, got
                 );
+                failed = true;
+            }
+        }
+        assert!(!failed);
+    }
+
+    #[test]
+    fn cmos_addressing_mode_test() {
+        let mut op_codes_to_addressing_mode: HashMap<u8, AddressingMode> = HashMap::new();
+
+        op_codes_to_addressing_mode.insert(0x02, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x03, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x04, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x07, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x0B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x0C, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x0F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x12, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0x13, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x14, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x17, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x1A, AddressingMode::Accumulator);
+        op_codes_to_addressing_mode.insert(0x1B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x1C, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x1F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x22, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x23, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x27, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x2B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x2F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x32, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0x33, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x34, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x37, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x3A, AddressingMode::Accumulator);
+        op_codes_to_addressing_mode.insert(0x3B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x3C, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x3F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x42, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x43, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x44, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x47, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x4B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x4F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x52, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0x53, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x54, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x57, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x5A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x5B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x5C, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x5F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x62, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x63, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x64, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x67, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x6B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x6F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x72, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0x73, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x74, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0x77, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x7A, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x7B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x7C, AddressingMode::AbsoluteIndexedIndirect);
+        op_codes_to_addressing_mode.insert(0x7F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x80, AddressingMode::Relative);
+        op_codes_to_addressing_mode.insert(0x82, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x83, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x87, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x89, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0x8B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x8F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0x92, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0x93, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x97, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0x9B, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0x9C, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0x9E, AddressingMode::AbsoluteX);
+        op_codes_to_addressing_mode.insert(0x9F, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xA3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xA7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xAB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xAF, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xB2, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0xB3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xB7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xBB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xBF, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xC2, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xC3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xC7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xCB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xCF, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xD2, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0xD3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xD4, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xD7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xDA, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xDB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xDC, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0xDF, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xE2, AddressingMode::Immediate);
+        op_codes_to_addressing_mode.insert(0xE3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xE7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xEB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xEF, AddressingMode::ZeroPageRelative);
+        op_codes_to_addressing_mode.insert(0xF2, AddressingMode::ZeroPageIndirect);
+        op_codes_to_addressing_mode.insert(0xF3, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xF4, AddressingMode::ZeroPageX);
+        op_codes_to_addressing_mode.insert(0xF7, AddressingMode::ZeroPage);
+        op_codes_to_addressing_mode.insert(0xFA, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xFB, AddressingMode::Implied);
+        op_codes_to_addressing_mode.insert(0xFC, AddressingMode::Absolute);
+        op_codes_to_addressing_mode.insert(0xFF, AddressingMode::ZeroPageRelative);
+
+        let mut failed = false;
+        for opcode in 0..=0xFF_u8 {
+            let expected = match op_codes_to_addressing_mode.get(&opcode) {
+                Some(mode) => *mode,
+                None => opcode_table::<MemoryBank>(CpuVariant::Nmos6502)[opcode as usize].mode,
+            };
+            let got = opcode_table::<MemoryBank>(CpuVariant::Cmos65C02)[opcode as usize].mode;
+            if got != expected {
+                println!(
+                    "Invalid 65C02 addressing mode for {:x} Expected: {:?}, Got: {:?}",
+                    opcode, expected, got
+                );
+                failed = true;
             }
         }
+        assert!(!failed);
     }
 }
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{self, AddressBus};
+    use crate::cpu::{BoxedMOS6502, InterruptType, MOS6502};
+    use std::fs::File;
+    use std::time::Instant;
+
+    const TEST_FILE_PATH: &str = "tests/6502_functional_test.bin";
+    const TEST_START_PC: u16 = 0x400;
+    const RUNS: usize = 5;
+
+    // Best emulated MHz over a few runs of Klaus Dormann's functional test
+    fn measure<B: AddressBus>(name: &str, cpu: &mut MOS6502<B>) {
+        let mut best = 0.0_f64;
+        for run in 0..RUNS {
+            cpu.interrupt(InterruptType::Reset).unwrap();
+            cpu.set_pc(TEST_START_PC);
+            let start_cycles = cpu.cycles();
+            let start = Instant::now();
+
+            while !cpu.is_trapped() {
+                cpu.step().unwrap();
+            }
+
+            let cycles = cpu.cycles() - start_cycles;
+            let mhz = cycles as f64 / start.elapsed().as_secs_f64() / 1_000_000.0;
+            println!("{} run {}: {} cycles at {:.1} MHz", name, run + 1, cycles, mhz);
+            best = best.max(mhz);
+        }
+
+        println!("{} best: {:.1} MHz", name, best);
+    }
+
+    // Run with `cargo test --release throughput -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn throughput_benchmark() {
+        let mut file = File::open(TEST_FILE_PATH).unwrap();
+        let memory = address_bus::memory_from_file(&mut file, false);
+        measure("Static bus", &mut MOS6502::new(memory));
+
+        let mut file = File::open(TEST_FILE_PATH).unwrap();
+        let memory = address_bus::memory_from_file(&mut file, false);
+        measure("Boxed bus", &mut BoxedMOS6502::new(Box::new(memory)));
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{
+        Breakpoint, BreakpointHit, BusOperation, CpuVariant, Register, RunOutcome, Trap,
+    };
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    #[test]
+    fn pc_breakpoint_test() {
+        // INX, JMP $0200
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xE8, 0x4C, 0x00, 0x02],
+        );
+        let id = cpu.add_breakpoint(Breakpoint::Pc(PROGRAM_START + 1));
+
+        for x in 1..=3 {
+            let outcome = cpu.run().unwrap();
+            assert_eq!(
+                outcome,
+                RunOutcome::Breakpoint(BreakpointHit {
+                    id,
+                    instruction_address: PROGRAM_START + 1,
+                    access: None,
+                })
+            );
+            // Stopped before the JMP, after the INX
+            assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+            assert_eq!(cpu.reg.ix, x);
+        }
+    }
+
+    #[test]
+    fn watchpoint_test() {
+        // LDA $10, STA $21, STA $30, JMP $0206
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xA5, 0x10, 0x85, 0x21, 0x85, 0x30, 0x4C, 0x06, 0x02],
+        );
+        cpu.bus.write(0x10, 0x5A);
+        let read = cpu.add_breakpoint(Breakpoint::Read(0x10..=0x10));
+        let write = cpu.add_breakpoint(Breakpoint::Write(0x20..=0x2F));
+
+        let RunOutcome::Breakpoint(hit) = cpu.run().unwrap() else {
+            panic!("Expected the read watchpoint to hit");
+        };
+        assert_eq!(hit.id, read);
+        assert_eq!(hit.instruction_address, PROGRAM_START);
+        let access = hit.access.unwrap();
+        assert_eq!((access.address, access.data), (0x10, 0x5A));
+        assert_eq!(access.operation, BusOperation::Read);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+
+        let RunOutcome::Breakpoint(hit) = cpu.run().unwrap() else {
+            panic!("Expected the write watchpoint to hit");
+        };
+        assert_eq!(hit.id, write);
+        assert_eq!(hit.instruction_address, PROGRAM_START + 2);
+        let access = hit.access.unwrap();
+        assert_eq!((access.address, access.data), (0x21, 0x5A));
+        assert_eq!(access.operation, BusOperation::Write);
+
+        // The STA $30 is outside both ranges, so it runs into the self-jump
+        assert_eq!(
+            cpu.run().unwrap(),
+            RunOutcome::Trapped(Trap::SelfJump {
+                address: PROGRAM_START + 6
+            })
+        );
+        assert_eq!(cpu.bus.read(0x30), 0x5A);
+    }
+
+    #[test]
+    fn register_breakpoint_test() {
+        // INX, INY, JMP $0200
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xE8, 0xC8, 0x4C, 0x00, 0x02],
+        );
+        let id = cpu.add_breakpoint(Breakpoint::Register(Register::Y, 0x03));
+
+        let outcome = cpu.run().unwrap();
+        assert_eq!(
+            outcome,
+            RunOutcome::Breakpoint(BreakpointHit {
+                id,
+                instruction_address: PROGRAM_START + 1,
+                access: None,
+            })
+        );
+        assert_eq!((cpu.reg.ix, cpu.reg.iy), (0x03, 0x03));
+
+        // Only when Y changes to it, so it hits again after wrapping around
+        cpu.run().unwrap();
+        assert_eq!((cpu.reg.ix, cpu.reg.iy), (0x03, 0x03));
+    }
+
+    #[test]
+    fn remove_breakpoint_test() {
+        // INX, JMP $0200
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xE8, 0x4C, 0x00, 0x02],
+        );
+        let pc = cpu.add_breakpoint(Breakpoint::Pc(PROGRAM_START));
+        let write = cpu.add_breakpoint(Breakpoint::Write(0x0000..=0xFFFF));
+        assert_eq!(cpu.breakpoints().len(), 2);
+
+        assert!(cpu.remove_breakpoint(write));
+        assert!(!cpu.remove_breakpoint(write));
+        assert_eq!(cpu.breakpoints(), &[(pc, Breakpoint::Pc(PROGRAM_START))]);
+
+        cpu.clear_breakpoints();
+        assert!(cpu.breakpoints().is_empty());
+
+        // A JAM instead of the JMP traps it
+        cpu.bus.write(PROGRAM_START + 1, 0x02);
+        assert_eq!(
+            cpu.run().unwrap(),
+            RunOutcome::Trapped(Trap::Jam {
+                address: PROGRAM_START + 1
+            })
+        );
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{BusAccess, BusOperation, CpuVariant, CPUFLAGS, MOS6502};
+    use crate::tests::cpu_with_program;
+    use BusOperation::{Read as R, Write as W};
+
+    const PROGRAM_START: u16 = 0x200;
+
+    fn logged_cpu(variant: CpuVariant, start: u16, program: &[u8]) -> MOS6502<MemoryBank> {
+        let mut cpu = cpu_with_program(variant, start, program);
+        cpu.reg.sp = 0xFD;
+        cpu.set_bus_log(true);
+        cpu
+    }
+
+    // Steps once and checks the accesses, one per cycle
+    fn check(cpu: &mut MOS6502<MemoryBank>, expected: &[(u16, u8, BusOperation)]) {
+        let start = cpu.cycles();
+        cpu.step().unwrap();
+
+        let expected: Vec<BusAccess> = expected
+            .iter()
+            .enumerate()
+            .map(|(i, &(address, data, operation))| BusAccess {
+                cycle: start + i as u64,
+                address,
+                data,
+                operation,
+            })
+            .collect();
+        assert_eq!(cpu.take_bus_log(), expected);
+    }
+
+    #[test]
+    fn absolute_x_rmw_test() {
+        // INC $12F0,X
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xFE, 0xF0, 0x12]);
+        cpu.bus.write(0x1310, 0x41);
+        cpu.reg.ix = 0x20;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0xFE, R),
+                (0x0201, 0xF0, R),
+                (0x0202, 0x12, R),
+                (0x1210, 0x00, R), // Before the high byte is fixed
+                (0x1310, 0x41, R),
+                (0x1310, 0x41, W), // Writes back the unmodified value
+                (0x1310, 0x42, W),
+            ],
+        );
+    }
+
+    #[test]
+    fn absolute_x_rmw_same_page_test() {
+        // INC $1200,X, the unfixed address is already the right one and is read twice
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xFE, 0x00, 0x12]);
+        cpu.bus.write(0x1220, 0x41);
+        cpu.reg.ix = 0x20;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0xFE, R),
+                (0x0201, 0x00, R),
+                (0x0202, 0x12, R),
+                (0x1220, 0x41, R),
+                (0x1220, 0x41, R),
+                (0x1220, 0x41, W),
+                (0x1220, 0x42, W),
+            ],
+        );
+    }
+
+    #[test]
+    fn cmos_rmw_test() {
+        // INC $12F0,X
+        let mut cpu = logged_cpu(CpuVariant::W65C02S, PROGRAM_START, &[0xFE, 0xF0, 0x12]);
+        cpu.bus.write(0x1310, 0x41);
+        cpu.reg.ix = 0x20;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0xFE, R),
+                (0x0201, 0xF0, R),
+                (0x0202, 0x12, R),
+                (0x0202, 0x12, R), // The last operand byte again
+                (0x1310, 0x41, R),
+                (0x1310, 0x41, R), // Reads instead of writing the unmodified value
+                (0x1310, 0x42, W),
+            ],
+        );
+    }
+
+    #[test]
+    fn cmos_page_cross_test() {
+        // LDA $12F0,Y; STA ($20),Y
+        let program = [0xB9, 0xF0, 0x12, 0x91, 0x20];
+        let mut cpu = logged_cpu(CpuVariant::W65C02S, PROGRAM_START, &program);
+        cpu.bus.write(0x20, 0x10);
+        cpu.bus.write(0x21, 0x12);
+        cpu.bus.write(0x130F, 0x55);
+        cpu.reg.iy = 0xFF;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0xB9, R),
+                (0x0201, 0xF0, R),
+                (0x0202, 0x12, R),
+                (0x0202, 0x12, R),
+                (0x13EF, 0x00, R),
+            ],
+        );
+        check(
+            &mut cpu,
+            &[
+                (0x0203, 0x91, R),
+                (0x0204, 0x20, R),
+                (0x0020, 0x10, R),
+                (0x0021, 0x12, R),
+                (0x0204, 0x20, R),
+                (0x130F, 0x00, W),
+            ],
+        );
+    }
+
+    #[test]
+    fn indirect_y_test() {
+        // LDA ($20),Y
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xB1, 0x20]);
+        cpu.bus.write(0x20, 0x10);
+        cpu.bus.write(0x21, 0x12);
+        cpu.bus.write(0x130F, 0x55);
+        cpu.reg.iy = 0xFF;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0xB1, R),
+                (0x0201, 0x20, R),
+                (0x0020, 0x10, R),
+                (0x0021, 0x12, R),
+                (0x120F, 0x00, R),
+                (0x130F, 0x55, R),
+            ],
+        );
+    }
+
+    #[test]
+    fn subroutine_test() {
+        // JSR $1234 then RTS
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0x20, 0x34, 0x12]);
+        cpu.bus.write(0x1234, 0x60);
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0x20, R),
+                (0x0201, 0x34, R),
+                (0x01FD, 0x00, R),
+                (0x01FD, 0x02, W),
+                (0x01FC, 0x02, W),
+                (0x0202, 0x12, R),
+            ],
+        );
+        check(
+            &mut cpu,
+            &[
+                (0x1234, 0x60, R),
+                (0x1235, 0x00, R),
+                (0x01FB, 0x00, R),
+                (0x01FC, 0x02, R),
+                (0x01FD, 0x02, R),
+                (0x0202, 0x12, R), // Reads the pulled address before incrementing it
+            ],
+        );
+    }
+
+    #[test]
+    fn stack_transfer_test() {
+        // TSX; TXS, a single dummy read after the opcode
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xBA, 0x9A]);
+        check(&mut cpu, &[(0x0200, 0xBA, R), (0x0201, 0x9A, R)]);
+        check(&mut cpu, &[(0x0201, 0x9A, R), (0x0202, 0x00, R)]);
+        assert_eq!(cpu.reg.ix, 0xFD);
+    }
+
+    #[test]
+    fn brk_test() {
+        // BRK
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0x00, 0xFF]);
+        cpu.reg.ps = CPUFLAGS::UNUSED;
+        check(
+            &mut cpu,
+            &[
+                (0x0200, 0x00, R),
+                (0x0201, 0xFF, R),
+                (0x01FD, 0x02, W),
+                (0x01FC, 0x02, W),
+                (0x01FB, 0x30, W),
+                (0xFFFE, 0x00, R),
+                (0xFFFF, 0x00, R),
+            ],
+        );
+    }
+
+    #[test]
+    fn branch_page_cross_test() {
+        // BNE $0300
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, 0x2FD, &[0xD0, 0x01]);
+        cpu.reg.ps.remove(CPUFLAGS::ZERO);
+        check(
+            &mut cpu,
+            &[
+                (0x02FD, 0xD0, R),
+                (0x02FE, 0x01, R),
+                (0x02FF, 0x00, R),
+                (0x0200, 0x00, R), // Before the high byte is fixed
+            ],
+        );
+    }
+
+    #[test]
+    fn bus_log_test() {
+        // NOP; NOP
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA, 0xEA]);
+        cpu.set_bus_log(false);
+        cpu.step().unwrap();
+        assert!(cpu.bus_log().is_empty());
+
+        // The reads repeated while RDY halts the CPU are logged too
+        cpu.set_bus_log(true);
+        cpu.stall(2);
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.bus_log()
+                .iter()
+                .map(|access| (access.cycle, access.address))
+                .collect::<Vec<_>>(),
+            vec![(2, 0x201), (3, 0x201), (4, 0x201), (5, 0x202)]
+        );
+    }
+
+    #[test]
+    fn mid_instruction_log_test() {
+        // LDA $1234, the log is turned on after the opcode fetch
+        let mut cpu = logged_cpu(CpuVariant::Nmos6502, PROGRAM_START, &[0xAD, 0x34, 0x12]);
+        cpu.set_bus_log(false);
+        cpu.tick_cycle().unwrap();
+        cpu.set_bus_log(true);
+        while cpu.tick_cycle().unwrap().is_none() {}
+
+        let addresses: Vec<u16> = cpu.bus_log().iter().map(|access| access.address).collect();
+        assert_eq!(addresses, vec![0x201, 0x202, 0x1234]);
+    }
+}
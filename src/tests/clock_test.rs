@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::MemoryBank;
+    use crate::clock::{ClockError, ClockMode};
+    use crate::cpu::{CpuVariant, MOS6502};
+    use crate::tests::load_program;
+    use std::time::{Duration, Instant};
+
+    const PROGRAM_START: u16 = 0x200;
+
+    // Runs a JMP $0200 loop for the given number of cycles and returns the host time it took
+    fn run_cycles(clock: ClockMode, cycles: u64) -> Duration {
+        let mut cpu = MOS6502::with_clock(MemoryBank::new(), CpuVariant::Nmos6502, clock).unwrap();
+        let [low, high] = PROGRAM_START.to_le_bytes();
+        load_program(&mut cpu, PROGRAM_START, &[0x4C, low, high]);
+        assert_eq!(cpu.clock_mode(), clock);
+
+        let start = Instant::now();
+        while cpu.cycles() < cycles {
+            cpu.step().unwrap();
+        }
+        start.elapsed()
+    }
+
+    // Sleeping never wakes up early so the throttled tests only check lower bounds, a loaded
+    // host can make any run arbitrarily slower
+    #[test]
+    fn real_time_clock_test() {
+        // 60000 cycles at 1 MHz is 60ms
+        let elapsed = run_cycles(ClockMode::RealTime(1_000_000.0), 60_000);
+        assert!(elapsed >= Duration::from_millis(55), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn scaled_clock_test() {
+        // Half speed, 30000 cycles take 60ms instead of 30ms
+        let elapsed = run_cycles(ClockMode::Scaled(1_000_000.0, 0.5), 30_000);
+        assert!(elapsed >= Duration::from_millis(55), "{:?}", elapsed);
+
+        // Twice as fast, 60000 cycles take 30ms
+        let elapsed = run_cycles(ClockMode::Scaled(1_000_000.0, 2.0), 60_000);
+        assert!(elapsed >= Duration::from_millis(25), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn unthrottled_clock_test() {
+        // A generous bound, throttled to 60 kHz these cycles would take a second
+        let elapsed = run_cycles(ClockMode::Unthrottled, 60_000);
+        assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn invalid_clock_test() {
+        let with_clock =
+            |clock| MOS6502::with_clock(MemoryBank::new(), CpuVariant::Nmos6502, clock);
+
+        for frequency in [0.0, -1_000_000.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                with_clock(ClockMode::RealTime(frequency)),
+                Err(ClockError::InvalidFrequency(_))
+            ));
+            assert!(matches!(
+                with_clock(ClockMode::Scaled(frequency, 1.0)),
+                Err(ClockError::InvalidFrequency(_))
+            ));
+            assert!(matches!(
+                with_clock(ClockMode::Scaled(1_000_000.0, frequency)),
+                Err(ClockError::InvalidMultiplier(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,364 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{CpuError, CpuVariant, InterruptType, StepOutcome, BRK_VECTOR, CPUFLAGS};
+    use crate::disassembler;
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    #[test]
+    fn branch_always_test() {
+        // BRA *+$12
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x80, 0x10]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 0x12);
+    }
+
+    #[test]
+    fn stack_xy_test() {
+        // PHX; PHY; PLX; PLY
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0xDA, 0x5A, 0xFA, 0x7A],
+        );
+        cpu.reg.ix = 0x12;
+        cpu.reg.iy = 0x80;
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.sp, 0xFD);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ix, 0x80);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::NEGATIVE));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.iy, 0x12);
+        assert_eq!(cpu.reg.sp, 0xFF);
+    }
+
+    #[test]
+    fn store_zero_test() {
+        // STZ $10; STZ $1234,X
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x64, 0x10, 0x9E, 0x34, 0x12],
+        );
+        cpu.bus.write(0x10, 0xFF);
+        cpu.bus.write(0x1235, 0xFF);
+        cpu.reg.ix = 0x01;
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x00);
+        assert_eq!(cpu.bus.read(0x1235), 0x00);
+    }
+
+    #[test]
+    fn test_and_set_reset_bits_test() {
+        // TSB $10; TRB $1234
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x04, 0x10, 0x1C, 0x34, 0x12],
+        );
+        cpu.bus.write(0x10, 0xF0);
+        cpu.bus.write(0x1234, 0xFF);
+        cpu.reg.ac = 0x0F;
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0xFF);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::ZERO));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x1234), 0xF0);
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::ZERO));
+    }
+
+    #[test]
+    fn zeropage_indirect_test() {
+        // LDA ($FF); STA ($20)
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0xB2, 0xFF, 0x92, 0x20],
+        );
+        cpu.bus.write(0xFF, 0x00);
+        cpu.bus.write(0x00, 0x30); // The pointer wraps around the zero page
+        cpu.bus.write(0x3000, 0x42);
+        cpu.bus.write(0x20, 0x00);
+        cpu.bus.write(0x21, 0x40);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x42);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x4000), 0x42);
+    }
+
+    #[test]
+    fn accumulator_inc_dec_test() {
+        // INC A; DEC A; DEC A
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x1A, 0x3A, 0x3A]);
+        cpu.reg.ac = 0xFF;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x00);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::ZERO));
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0xFE);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::NEGATIVE));
+    }
+
+    #[test]
+    fn bit_immediate_test() {
+        // BIT #$C0
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x89, 0xC0]);
+        cpu.reg.ac = 0x01;
+        cpu.step().unwrap();
+        assert!(cpu.reg.ps.contains(CPUFLAGS::ZERO));
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::NEGATIVE));
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+    }
+
+    #[test]
+    fn jmp_absolute_indexed_indirect_test() {
+        // JMP ($1000,X)
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x7C, 0x00, 0x10]);
+        cpu.bus.write(0x1004, 0x78);
+        cpu.bus.write(0x1005, 0x56);
+        cpu.reg.ix = 0x04;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x5678);
+    }
+
+    #[test]
+    fn unused_opcode_test() {
+        // $03 is a single byte NOP, $DC a three byte one
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x03, 0xDC, 0x00, 0x00],
+        );
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 4);
+    }
+
+    #[test]
+    fn interrupt_clears_decimal_test() {
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0xF8, 0x00]);
+        cpu.bus.write(BRK_VECTOR, 0x00);
+        cpu.bus.write(BRK_VECTOR + 1, 0x30);
+        cpu.step().unwrap();
+        assert!(cpu.reg.ps.contains(CPUFLAGS::DECIMAL));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x3000);
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::DECIMAL));
+
+        // But not on the NMOS 6502
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xF8, 0x00]);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(cpu.reg.ps.contains(CPUFLAGS::DECIMAL));
+    }
+
+    #[test]
+    fn cmos_disassembly_test() {
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0xB2, 0x12, 0x7C, 0x34, 0x12],
+        );
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x200, CpuVariant::W65C02S);
+        assert_eq!(disassembly.unwrap(), "$0200 | B2 12    | LDA ($12)");
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x202, CpuVariant::W65C02S);
+        assert_eq!(disassembly.unwrap(), "$0202 | 7C 34 12 | JMP ($1234,X)");
+
+        let disassembly = disassembler::disassemble_instruction(&mut cpu.bus, 0x200);
+        assert_eq!(disassembly.unwrap(), "$0200 | B2       | JAM ");
+    }
+
+    #[test]
+    fn reset_set_memory_bit_test() {
+        // RMB3 $10; SMB7 $10
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x37, 0x10, 0xF7, 0x10],
+        );
+        cpu.bus.write(0x10, 0x0F);
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x07);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x87);
+    }
+
+    #[test]
+    fn branch_on_bit_test() {
+        // BBR0 $10,*+$8; BBS0 $10,*+$8
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x0F, 0x10, 0x05, 0x8F, 0x10, 0x05],
+        );
+        cpu.bus.write(0x10, 0x01);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3 + 8);
+    }
+
+    #[test]
+    fn wait_for_interrupt_test() {
+        // WAI; NOP
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0xCB, 0xEA]);
+        cpu.bus.write(BRK_VECTOR, 0x00);
+        cpu.bus.write(BRK_VECTOR + 1, 0x30);
+        cpu.step().unwrap();
+        assert!(cpu.is_waiting());
+
+        assert_eq!(cpu.step(), Ok(StepOutcome::Waiting(1)));
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        assert!(!cpu.is_waiting());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, 0x3000);
+    }
+
+    #[test]
+    fn cmos_undocumented_opcodes_test() {
+        // SMB0 $10 is reserved on the 65SC02 and documented on the 65C02
+        let mut cpu = cpu_with_program(CpuVariant::Cmos65SC02, PROGRAM_START, &[0x87, 0x10]);
+        cpu.set_undocumented_opcodes(false);
+        assert!(matches!(
+            cpu.step(),
+            Err(CpuError::IllegalOpcode { opcode: 0x87, .. })
+        ));
+
+        let mut cpu = cpu_with_program(CpuVariant::Cmos65C02, PROGRAM_START, &[0x87, 0x10]);
+        cpu.set_undocumented_opcodes(false);
+        assert_eq!(cpu.step(), Ok(StepOutcome::Instruction(5)));
+    }
+
+    #[test]
+    fn wait_for_masked_interrupt_test() {
+        // WAI; NOP
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0xCB, 0xEA]);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.step().unwrap();
+        assert!(cpu.is_waiting());
+
+        // A masked IRQ still ends WAI but execution just continues
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        assert!(!cpu.is_waiting());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn stop_test() {
+        // STP; NOP
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0xDB, 0xEA]);
+        assert_eq!(
+            cpu.step(),
+            Err(CpuError::Stopped {
+                address: PROGRAM_START
+            })
+        );
+        assert!(cpu.is_stopped());
+
+        assert!(cpu.step().is_err());
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+
+        cpu.interrupt(InterruptType::Reset).unwrap();
+        assert!(!cpu.is_stopped());
+    }
+
+    #[test]
+    fn zeropage_relative_disassembly_test() {
+        let mut cpu = cpu_with_program(
+            CpuVariant::W65C02S,
+            PROGRAM_START,
+            &[0x8F, 0x12, 0x05, 0xAF, 0x12, 0xFD],
+        );
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x200, CpuVariant::W65C02S);
+        assert_eq!(
+            disassembly.unwrap(),
+            "$0200 | 8F 12 05 | BBS0 $12,*+$5    ; $0205"
+        );
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x203, CpuVariant::W65C02S);
+        assert_eq!(
+            disassembly.unwrap(),
+            "$0203 | AF 12 FD | BBS2 $12,*    ; $0203"
+        );
+    }
+
+    #[test]
+    fn variant_extensions_test() {
+        // SMB0 $10; WAI
+        let program = [0x87, 0x10, 0xCB];
+
+        // The 65SC02 has neither, both are single byte NOPs
+        let mut cpu = cpu_with_program(CpuVariant::Cmos65SC02, PROGRAM_START, &program);
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x00);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+        cpu.set_pc(PROGRAM_START + 2);
+        cpu.step().unwrap();
+        assert!(!cpu.is_waiting());
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+
+        // The Rockwell 65C02 has the bit instructions but no WAI
+        let mut cpu = cpu_with_program(CpuVariant::Cmos65C02, PROGRAM_START, &program);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x01);
+        assert!(!cpu.is_waiting());
+
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &program);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x10), 0x01);
+        assert!(cpu.is_waiting());
+    }
+
+    #[test]
+    fn variant_disassembly_test() {
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x87, 0x10, 0xCB]);
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x200, CpuVariant::Cmos65SC02);
+        assert_eq!(disassembly.unwrap(), "$0200 | 87       | NOP ");
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x200, CpuVariant::Cmos65C02);
+        assert_eq!(disassembly.unwrap(), "$0200 | 87 10    | SMB0 $10");
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x202, CpuVariant::Cmos65C02);
+        assert_eq!(disassembly.unwrap(), "$0202 | CB       | NOP ");
+
+        let disassembly =
+            disassembler::disassemble_instruction_as(&mut cpu.bus, 0x202, CpuVariant::Ricoh2A03);
+        assert_eq!(disassembly.unwrap(), "$0202 | CB 00    | SBX #$00");
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::opcode_modes::AddressingMode;
+    use crate::cpu::opcode_table::opcode_table;
+    use crate::cpu::{CpuVariant, CPUFLAGS};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    // NMOS 6502 cycle counts, branches are listed as not taken and JAMs as 0
+    #[rustfmt::skip]
+    const NMOS_CYCLES: [u64; 256] = [
+    //  0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+        7, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6, // 0
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 1
+        6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6, // 2
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 3
+        6, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6, // 4
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 5
+        6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6, // 6
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 7
+        2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // 8
+        2, 6, 0, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5, // 9
+        2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // A
+        2, 5, 0, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4, // B
+        2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // C
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // D
+        2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // E
+        2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // F
+    ];
+
+    // Extra cycle taken when the indexed address crosses a page
+    #[rustfmt::skip]
+    const NMOS_PAGE_CROSS: [u64; 256] = [
+    //  0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 0
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // 1
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 2
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // 3
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 4
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // 5
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 6
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // 7
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 8
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 9
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // A
+        0, 1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 1, 1, 1, 1, // B
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // C
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // D
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // E
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, // F
+    ];
+
+    // W65C02S cycle counts, branches are listed as not taken, BBR, BBS and STP as 0
+    #[rustfmt::skip]
+    const CMOS_CYCLES: [u64; 256] = [
+    //  0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+        7, 6, 2, 1, 5, 3, 5, 5, 3, 2, 2, 1, 6, 4, 6, 0, // 0
+        2, 5, 5, 1, 5, 4, 6, 5, 2, 4, 2, 1, 6, 4, 6, 0, // 1
+        6, 6, 2, 1, 3, 3, 5, 5, 4, 2, 2, 1, 4, 4, 6, 0, // 2
+        2, 5, 5, 1, 4, 4, 6, 5, 2, 4, 2, 1, 4, 4, 6, 0, // 3
+        6, 6, 2, 1, 3, 3, 5, 5, 3, 2, 2, 1, 3, 4, 6, 0, // 4
+        2, 5, 5, 1, 4, 4, 6, 5, 2, 4, 3, 1, 8, 4, 6, 0, // 5
+        6, 6, 2, 1, 3, 3, 5, 5, 4, 2, 2, 1, 6, 4, 6, 0, // 6
+        2, 5, 5, 1, 4, 4, 6, 5, 2, 4, 4, 1, 6, 4, 6, 0, // 7
+        3, 6, 2, 1, 3, 3, 3, 5, 2, 2, 2, 1, 4, 4, 4, 0, // 8
+        2, 6, 5, 1, 4, 4, 4, 5, 2, 5, 2, 1, 4, 5, 5, 0, // 9
+        2, 6, 2, 1, 3, 3, 3, 5, 2, 2, 2, 1, 4, 4, 4, 0, // A
+        2, 5, 5, 1, 4, 4, 4, 5, 2, 4, 2, 1, 4, 4, 4, 0, // B
+        2, 6, 2, 1, 3, 3, 5, 5, 2, 2, 2, 3, 4, 4, 6, 0, // C
+        2, 5, 5, 1, 4, 4, 6, 5, 2, 4, 3, 0, 4, 4, 7, 0, // D
+        2, 6, 2, 1, 3, 3, 5, 5, 2, 2, 2, 1, 4, 4, 6, 0, // E
+        2, 5, 5, 1, 4, 4, 6, 5, 2, 4, 4, 1, 4, 4, 7, 0, // F
+    ];
+
+    #[rustfmt::skip]
+    const CMOS_PAGE_CROSS: [u64; 256] = [
+    //  0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 0
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, // 1
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 2
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 1, 0, // 3
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 4
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, // 5
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 6
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 0, // 7
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 8
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 9
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // A
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 1, 1, 0, // B
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // C
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, // D
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // E
+        0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, // F
+    ];
+
+    fn is_branch(opcode: u8) -> bool {
+        opcode & 0x1F == 0x10
+    }
+
+    // Runs a single instruction with both operand bytes set to $01 and returns its cycle count
+    fn cycles_for(opcode: u8, index: u8) -> u64 {
+        cycles_for_variant(opcode, index, CpuVariant::Nmos6502)
+    }
+
+    fn cycles_for_variant(opcode: u8, index: u8, variant: CpuVariant) -> u64 {
+        let mut cpu = cpu_with_program(variant, PROGRAM_START, &[opcode, 0x01, 0x00]);
+
+        // ($01),Y points at $0001
+        cpu.bus.write(0x01, 0x01);
+        cpu.bus.write(0x02, 0x00);
+
+        cpu.reg.ix = index;
+        cpu.reg.iy = index;
+        cpu.reg.sp = 0xFD;
+        cpu.step().unwrap().cycles()
+    }
+
+    #[test]
+    fn nmos_cycle_timing_test() {
+        for opcode in 0..=0xFF_u8 {
+            let expected = NMOS_CYCLES[opcode as usize];
+            if expected == 0 || is_branch(opcode) {
+                continue;
+            }
+
+            assert_eq!(cycles_for(opcode, 0x00), expected, "opcode ${:02X}", opcode);
+            assert_eq!(
+                cycles_for(opcode, 0xFF),
+                expected + NMOS_PAGE_CROSS[opcode as usize],
+                "opcode ${:02X} crossing a page",
+                opcode
+            );
+        }
+    }
+
+    #[test]
+    fn cmos_cycle_timing_test() {
+        let variants = [
+            CpuVariant::Cmos65SC02,
+            CpuVariant::Cmos65C02,
+            CpuVariant::W65C02S,
+        ];
+        for variant in variants {
+            for opcode in 0..=0xFF_u8 {
+                let expected = match opcode {
+                    // Single cycle NOPs without WAI and STP or the bit instructions
+                    0xCB | 0xDB if variant != CpuVariant::W65C02S => 1,
+                    _ if opcode & 0b111 == 0b111 && variant == CpuVariant::Cmos65SC02 => 1,
+                    _ => CMOS_CYCLES[opcode as usize],
+                };
+                if expected == 0 || is_branch(opcode) {
+                    continue;
+                }
+
+                assert_eq!(
+                    cycles_for_variant(opcode, 0x00, variant),
+                    expected,
+                    "{:?} opcode ${:02X}",
+                    variant,
+                    opcode
+                );
+                assert_eq!(
+                    cycles_for_variant(opcode, 0xFF, variant),
+                    expected + CMOS_PAGE_CROSS[opcode as usize],
+                    "{:?} opcode ${:02X} crossing a page",
+                    variant,
+                    opcode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn opcode_table_cycles_test() {
+        // The disassembler and tracer rely on the table agreeing with the CPU
+        let tables = [
+            (CpuVariant::Nmos6502, &NMOS_CYCLES),
+            (CpuVariant::W65C02S, &CMOS_CYCLES),
+        ];
+        for (variant, cycles) in tables {
+            for (opcode, entry) in opcode_table::<MemoryBank>(variant).iter().enumerate() {
+                let skipped = matches!(entry.mode, AddressingMode::ZeroPageRelative)
+                    || entry.mnemonic == "STP";
+                if !skipped {
+                    assert_eq!(
+                        entry.cycles as u64, cycles[opcode],
+                        "{:?} opcode ${:02X} ({})",
+                        variant, opcode, entry.mnemonic
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn branch_cycle_timing_test() {
+        let branch = |offset: u8, start: u16| {
+            // BNE offset
+            let mut cpu = cpu_with_program(CpuVariant::Nmos6502, start, &[0xD0, offset]);
+            cpu.reg.ps.remove(CPUFLAGS::ZERO);
+            let taken = cpu.step().unwrap().cycles();
+
+            let mut cpu = cpu_with_program(CpuVariant::Nmos6502, start, &[0xD0, offset]);
+            cpu.reg.ps.insert(CPUFLAGS::ZERO);
+            (taken, cpu.step().unwrap().cycles())
+        };
+
+        assert_eq!(branch(0x10, 0x200), (3, 2));
+        assert_eq!(branch(0x7F, 0x2F0), (4, 2));
+        assert_eq!(branch(0x80, 0x210), (4, 2));
+    }
+
+    #[test]
+    fn cycle_counter_test() {
+        // LDA #$01; STA $1234; INC $1234,X
+        let program = [0xA9, 0x01, 0x8D, 0x34, 0x12, 0xFE, 0x34, 0x12];
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &program);
+
+        assert_eq!(cpu.step().unwrap().cycles(), 2);
+        assert_eq!(cpu.step().unwrap().cycles(), 4);
+        assert_eq!(cpu.step().unwrap().cycles(), 7);
+        assert_eq!(cpu.cycles(), 13);
+    }
+
+    #[test]
+    fn cmos_jmp_indirect_cycle_test() {
+        // JMP ($1234)
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x6C, 0x34, 0x12]);
+        assert_eq!(cpu.step().unwrap().cycles(), 6);
+    }
+
+    // Instructions the baseline took the wrong number of cycles for
+    #[test]
+    fn baseline_cycle_regression_test() {
+        // The page cross check compared the new PC with itself and never added the fixup cycle
+        // BNE $0300
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, 0x2FD, &[0xD0, 0x01]);
+        cpu.reg.ps.remove(CPUFLAGS::ZERO);
+        assert_eq!(cpu.step().unwrap().cycles(), 4);
+        assert_eq!(cpu.reg.pc, 0x300);
+
+        // RTS repeated the dummy read instruction_implied() already does
+        assert_eq!(cycles_for(0x60, 0), 6);
+
+        // TSX and TXS ticked again after the dummy read
+        assert_eq!(cycles_for(0xBA, 0), 2);
+        assert_eq!(cycles_for(0x9A, 0), 2);
+
+        // JSR went through instruction_implied(), adding a dummy read in front of its own
+        assert_eq!(cycles_for(0x20, 0), 6);
+    }
+}
@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::MemoryBank;
+    use crate::cpu::{BoxedMOS6502, CpuVariant, CPUFLAGS};
+    use crate::tests::load_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    // Reference model from Bruce Clark's decimal mode tutorial and test program
+    // (http://www.6502.org/tutorials/decimal_mode.html, Appendix B)
+    struct Expected {
+        ac: u8,
+        negative: bool,
+        overflow: bool,
+        zero: bool,
+        carry: bool,
+    }
+
+    fn predict_adc(a: u8, b: u8, carry: bool) -> Expected {
+        let (a, b, c) = (a as i32, b as i32, carry as i32);
+
+        // Seq. 1 (accumulator and carry)
+        let mut al = (a & 0x0F) + (b & 0x0F) + c;
+        if al >= 0x0A {
+            al = ((al + 0x06) & 0x0F) + 0x10;
+        }
+        let mut result = (a & 0xF0) + (b & 0xF0) + al;
+        if result >= 0xA0 {
+            result += 0x60;
+        }
+
+        // Seq. 2 (N and V)
+        let mut al = (a & 0x0F) + (b & 0x0F) + c;
+        if al >= 0x0A {
+            al = ((al + 0x06) & 0x0F) + 0x10;
+        }
+        let signed = (a as u8 as i8 as i32 & !0x0F) + (b as u8 as i8 as i32 & !0x0F) + al;
+
+        Expected {
+            ac: result as u8,
+            negative: (signed & 0x80) != 0,
+            overflow: !(-128..=127).contains(&signed),
+            zero: ((a + b + c) & 0xFF) == 0,
+            carry: result >= 0x100,
+        }
+    }
+
+    fn predict_sbc(a: u8, b: u8, carry: bool) -> Expected {
+        let (a, b, c) = (a as i32, b as i32, carry as i32);
+
+        // Seq. 3 (accumulator)
+        let mut al = (a & 0x0F) - (b & 0x0F) + c - 1;
+        if al < 0 {
+            al = ((al - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result = (a & 0xF0) - (b & 0xF0) + al;
+        if result < 0 {
+            result -= 0x60;
+        }
+
+        // Flags are the same as binary mode
+        let binary = a - b + c - 1;
+        let signed = a as u8 as i8 as i32 - b as u8 as i8 as i32 + c - 1;
+
+        Expected {
+            ac: result as u8,
+            negative: (binary & 0x80) != 0,
+            overflow: !(-128..=127).contains(&signed),
+            zero: (binary & 0xFF) == 0,
+            carry: binary >= 0,
+        }
+    }
+
+    // The 65C02 takes N and Z from the accumulator and adjusts SBC in a single step
+    fn predict_cmos_adc(a: u8, b: u8, carry: bool) -> Expected {
+        let expected = predict_adc(a, b, carry);
+        Expected {
+            negative: (expected.ac & 0x80) != 0,
+            zero: expected.ac == 0,
+            ..expected
+        }
+    }
+
+    fn predict_cmos_sbc(a: u8, b: u8, carry: bool) -> Expected {
+        let expected = predict_sbc(a, b, carry);
+        let (a, b, c) = (a as i32, b as i32, carry as i32);
+
+        // Seq. 4 (accumulator)
+        let al = (a & 0x0F) - (b & 0x0F) + c - 1;
+        let mut result = a - b + c - 1;
+        if result < 0 {
+            result -= 0x60;
+        }
+        if al < 0 {
+            result -= 0x06;
+        }
+
+        Expected {
+            ac: result as u8,
+            negative: (result & 0x80) != 0,
+            zero: (result & 0xFF) == 0,
+            ..expected
+        }
+    }
+
+    fn run_decimal(cpu: &mut BoxedMOS6502, opcode: u8, a: u8, b: u8, carry: bool) {
+        load_program(cpu, PROGRAM_START, &[opcode, b]);
+        cpu.reg.ac = a;
+        cpu.reg.ps = CPUFLAGS::UNUSED | CPUFLAGS::DECIMAL;
+        cpu.reg.ps.set(CPUFLAGS::CARRY, carry);
+        cpu.step().unwrap();
+    }
+
+    fn check(cpu: &BoxedMOS6502, expected: Expected, name: &str, a: u8, b: u8, carry: bool) {
+        let ps = cpu.reg.ps;
+        let context = format!("{} A={:02X} M={:02X} C={}", name, a, b, carry as u8);
+        assert_eq!(cpu.reg.ac, expected.ac, "{}: accumulator", context);
+        assert_eq!(
+            ps.contains(CPUFLAGS::NEGATIVE),
+            expected.negative,
+            "{}: N",
+            context
+        );
+        assert_eq!(
+            ps.contains(CPUFLAGS::OVERFLOW),
+            expected.overflow,
+            "{}: V",
+            context
+        );
+        assert_eq!(ps.contains(CPUFLAGS::ZERO), expected.zero, "{}: Z", context);
+        assert_eq!(
+            ps.contains(CPUFLAGS::CARRY),
+            expected.carry,
+            "{}: C",
+            context
+        );
+    }
+
+    #[test]
+    fn decimal_mode_test() {
+        let mut cpu = BoxedMOS6502::new(Box::new(MemoryBank::new()));
+
+        for a in 0..=0xFF_u8 {
+            for b in 0..=0xFF_u8 {
+                for carry in [false, true] {
+                    run_decimal(&mut cpu, 0x69, a, b, carry);
+                    check(&cpu, predict_adc(a, b, carry), "ADC", a, b, carry);
+
+                    run_decimal(&mut cpu, 0xE9, a, b, carry);
+                    check(&cpu, predict_sbc(a, b, carry), "SBC", a, b, carry);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cmos_decimal_mode_test() {
+        let mut cpu =
+            BoxedMOS6502::with_variant(Box::new(MemoryBank::new()), CpuVariant::Cmos65C02);
+
+        for a in 0..=0xFF_u8 {
+            for b in 0..=0xFF_u8 {
+                for carry in [false, true] {
+                    run_decimal(&mut cpu, 0x69, a, b, carry);
+                    check(&cpu, predict_cmos_adc(a, b, carry), "ADC", a, b, carry);
+
+                    run_decimal(&mut cpu, 0xE9, a, b, carry);
+                    check(&cpu, predict_cmos_sbc(a, b, carry), "SBC", a, b, carry);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ricoh_decimal_mode_test() {
+        // The 2A03 ignores the decimal flag
+        let mut cpu =
+            BoxedMOS6502::with_variant(Box::new(MemoryBank::new()), CpuVariant::Ricoh2A03);
+
+        run_decimal(&mut cpu, 0x69, 0x09, 0x01, false);
+        assert_eq!(cpu.reg.ac, 0x0A);
+
+        run_decimal(&mut cpu, 0xE9, 0x10, 0x01, true);
+        assert_eq!(cpu.reg.ac, 0x0F);
+    }
+}
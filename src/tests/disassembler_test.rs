@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::CpuVariant;
+    use crate::disassembler;
+
+    fn disassemble(variant: CpuVariant, address: u16, bytes: &[u8]) -> String {
+        let mut memory = MemoryBank::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            memory.write(address + i as u16, *byte);
+        }
+        disassembler::disassemble_instruction_as(&mut memory, address, variant).unwrap()
+    }
+
+    #[test]
+    fn branch_operand_test() {
+        // The raw offset, the target is the address of the branch plus the offset
+        let branch = |bytes: &[u8]| disassemble(CpuVariant::Nmos6502, 0x200, bytes);
+        assert_eq!(
+            branch(&[0xD0, 0x10]),
+            "$0200 | D0 10    | BNE *+$10    ; $0210"
+        );
+        assert_eq!(
+            branch(&[0xD0, 0x00]),
+            "$0200 | D0 00    | BNE *+$0    ; $0200"
+        );
+        assert_eq!(branch(&[0xD0, 0xFE]), "$0200 | D0 FE    | BNE *    ; $0200");
+        assert_eq!(
+            branch(&[0xD0, 0xF0]),
+            "$0200 | D0 F0    | BNE *-$10    ; $01F0"
+        );
+
+        // BBR0 $12 uses the same format for its offset
+        let disassembly = disassemble(CpuVariant::W65C02S, 0x200, &[0x0F, 0x12, 0x10]);
+        assert_eq!(disassembly, "$0200 | 0F 12 10 | BBR0 $12,*+$10    ; $0210");
+    }
+
+    #[test]
+    fn backward_branch_operand_test() {
+        // Offsets with bit 7 set go backwards whatever the length of the instruction
+        let branch = |offset: u8| disassemble(CpuVariant::Nmos6502, 0x200, &[0xD0, offset]);
+        assert_eq!(branch(0xFF), "$0200 | D0 FF    | BNE *-$1    ; $01FF");
+        assert_eq!(branch(0x80), "$0200 | D0 80    | BNE *-$80    ; $0180");
+
+        let bbr = |offset: u8| disassemble(CpuVariant::W65C02S, 0x200, &[0x0F, 0x12, offset]);
+        assert_eq!(bbr(0xFD), "$0200 | 0F 12 FD | BBR0 $12,*    ; $0200");
+        assert_eq!(bbr(0xFE), "$0200 | 0F 12 FE | BBR0 $12,*-$2    ; $01FE");
+        assert_eq!(bbr(0xFF), "$0200 | 0F 12 FF | BBR0 $12,*-$1    ; $01FF");
+        assert_eq!(bbr(0x80), "$0200 | 0F 12 80 | BBR0 $12,*-$80    ; $0180");
+    }
+
+    #[test]
+    fn end_of_memory_test() {
+        // The operand bytes wrap around to $0000
+        let mut memory = MemoryBank::new();
+        memory.write(0xFFFF, 0xAD);
+        memory.write(0x0000, 0x34);
+        memory.write(0x0001, 0x12);
+        assert_eq!(
+            disassembler::disassemble_instruction(&mut memory, 0xFFFF).unwrap(),
+            "$FFFF | AD 34 12 | LDA $1234"
+        );
+
+        for opcode in 0..=0xFF_u8 {
+            memory.write(0xFFFF, opcode);
+            disassembler::disassemble_instruction_as(&mut memory, 0xFFFF, CpuVariant::W65C02S);
+            disassembler::disassemble_instruction_as(&mut memory, 0xFFFE, CpuVariant::Nmos6502);
+        }
+    }
+}
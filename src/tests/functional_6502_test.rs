@@ -1,48 +1,81 @@
 #[cfg(test)]
 mod tests {
-    use crate::address_bus;
+    use crate::address_bus::{self, AddressBus, MemoryBank};
     use crate::cpu;
-    use crate::disassembler;
-    use std::collections::VecDeque;
+    use crate::tracer::{TraceFilter, Tracer};
     use std::fs::File;
+    use std::io;
 
-    #[test]
-    fn functional_test() {
-        const TEST_FILE_PATH: &str = "tests\\6502_functional_test.bin";
-        const TEST_END_PC: u16 = 0x336D;
-        const TEST_START_PC: u16 = 0x400;
+    const TEST_START_PC: u16 = 0x400;
 
-        let mut file = File::open(TEST_FILE_PATH).unwrap();
+    // Runs one of Klaus Dormann's functional test binaries until it traps, failing the test
+    // unless it trapped at the success address
+    fn run_functional_test(path: &str, end_pc: u16) {
+        let mut file = File::open(path).unwrap();
 
         let memory = address_bus::memory_from_file(&mut file, true);
         let mut cpu = cpu::MOS6502::new(Box::new(memory));
-        cpu.interrupt(cpu::InterruptType::Reset);
+        cpu.interrupt(cpu::InterruptType::Reset).unwrap();
         cpu.set_pc(TEST_START_PC);
+        let start = cpu.save_state();
 
-        let mut past_registers: VecDeque<cpu::MOS6502Registers> = VecDeque::new();
-
+        // Counts the instructions without writing anything, formatting every one of them
+        // would make the test far slower
+        let filter = TraceFilter {
+            instructions: Some(0..0),
+            ..Default::default()
+        };
+        let mut tracer = Tracer::new(io::sink(), filter);
         while !cpu.is_trapped() {
-            cpu.step();
-            past_registers.push_back(cpu.reg.clone());
-            if past_registers.len() > 64 {
-                past_registers.pop_front();
-            }
+            tracer.trace(&mut cpu).unwrap();
+            cpu.step().unwrap();
         }
 
-        if cpu.reg.pc != TEST_END_PC {
-            for reg in past_registers {
-                let disassembly = disassembler::disassemble_instruction(&mut cpu.bus, reg.pc);
-                if let Some(str) = disassembly {
-                    println!(
-                        "A: {:02X} X: {:02X} Y: {:02X} | {}",
-                        reg.ac, reg.ix, reg.iy, str
-                    )
-                }
+        if cpu.reg.pc != end_pc {
+            // Runs it again from the start, tracing the last instructions into a buffer
+            let instructions = tracer.instructions();
+            let filter = TraceFilter {
+                instructions: Some(instructions.saturating_sub(64)..instructions),
+                ..Default::default()
+            };
+            let mut tracer = Tracer::new(Vec::new(), filter);
+            cpu.load_state(&start).unwrap();
+            while !cpu.is_trapped() {
+                tracer.trace(&mut cpu).unwrap();
+                cpu.step().unwrap();
             }
 
+            print!("{}", String::from_utf8(tracer.into_inner()).unwrap());
             panic!("Test Failed!");
         }
+    }
+
+    #[test]
+    fn functional_test() {
+        run_functional_test("tests/6502_functional_test.bin", 0x336D);
+    }
+
+    // The same source assembled with disable_decimal = 0, which adds the ADC and SBC tests in
+    // decimal mode over every valid BCD operand and carry in
+    #[test]
+    fn functional_decimal_test() {
+        run_functional_test("tests/6502_functional_test_decimal.bin", 0x3469);
+    }
+
+    #[test]
+    fn borrowed_bus_test() {
+        // LDA #$42; STA $10 on memory the test keeps ownership of
+        let mut memory = MemoryBank::new();
+        memory.write(0x200, 0xA9);
+        memory.write(0x201, 0x42);
+        memory.write(0x202, 0x85);
+        memory.write(0x203, 0x10);
+
+        let mut cpu = cpu::MOS6502::new(&mut memory);
+        cpu.set_pc(0x200);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
 
-        println!("Tested Passed! :D");
+        assert_eq!(memory.read(0x10), 0x42);
     }
 }
@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::opcode_modes;
+    use crate::cpu::{BoxedMOS6502, CpuError, CpuVariant, InterruptType, StepOutcome, CPUFLAGS};
+    use crate::tests::load_program;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    type BusLog = Rc<RefCell<Vec<(u16, u8, bool)>>>;
+
+    // Memory that records every access as (address, value, is_write)
+    struct LoggingBus {
+        memory: MemoryBank,
+        log: BusLog,
+    }
+
+    impl AddressBus for LoggingBus {
+        fn read(&mut self, address: u16) -> u8 {
+            let value = self.memory.read(address);
+            self.log.borrow_mut().push((address, value, false));
+            value
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            self.log.borrow_mut().push((address, value, true));
+            self.memory.write(address, value);
+        }
+    }
+
+    fn logging_cpu(program: &[u8]) -> (BoxedMOS6502, BusLog) {
+        let log = BusLog::default();
+        let bus = LoggingBus {
+            memory: MemoryBank::new(),
+            log: log.clone(),
+        };
+        let mut cpu = BoxedMOS6502::new(Box::new(bus));
+        load_program(&mut cpu, PROGRAM_START, program);
+        log.borrow_mut().clear();
+        (cpu, log)
+    }
+
+    #[test]
+    fn lax_sax_test() {
+        // LAX $10; SAX $11
+        let (mut cpu, _) = logging_cpu(&[0xA7, 0x10, 0x87, 0x11]);
+        cpu.bus.write(0x10, 0xF3);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0xF3);
+        assert_eq!(cpu.reg.ix, 0xF3);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::NEGATIVE));
+
+        cpu.reg.ac = 0x3C;
+        cpu.step().unwrap();
+        assert_eq!(cpu.bus.read(0x11), 0x30);
+    }
+
+    #[test]
+    fn dcp_absolute_y_bus_test() {
+        // DCP $12F0,Y
+        let (mut cpu, log) = logging_cpu(&[0xDB, 0xF0, 0x12]);
+        cpu.bus.write(0x1310, 0x41);
+        log.borrow_mut().clear();
+        cpu.reg.iy = 0x20;
+        cpu.reg.ac = 0x40;
+        cpu.step().unwrap();
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                (0x200, 0xDB, false),
+                (0x201, 0xF0, false),
+                (0x202, 0x12, false),
+                (0x1210, 0x00, false), // Read before the high byte is fixed
+                (0x1310, 0x41, false),
+                (0x1310, 0x41, true), // Write back the unmodified value
+                (0x1310, 0x40, true),
+            ]
+        );
+        assert!(cpu.reg.ps.contains(CPUFLAGS::ZERO));
+        assert!(cpu.reg.ps.contains(CPUFLAGS::CARRY));
+    }
+
+    #[test]
+    fn isc_indirect_y_test() {
+        // ISC ($20),Y
+        let (mut cpu, log) = logging_cpu(&[0xF3, 0x20]);
+        cpu.bus.write(0x20, 0x00);
+        cpu.bus.write(0x21, 0x30);
+        cpu.bus.write(0x3005, 0x0F);
+        log.borrow_mut().clear();
+        cpu.reg.iy = 0x05;
+        cpu.reg.ac = 0x20;
+        cpu.reg.ps.insert(CPUFLAGS::CARRY);
+        cpu.step().unwrap();
+
+        assert_eq!(log.borrow().len(), 8);
+        assert_eq!(cpu.bus.read(0x3005), 0x10);
+        assert_eq!(cpu.reg.ac, 0x10);
+    }
+
+    #[test]
+    fn shx_page_cross_test() {
+        // SHX $34F0,Y
+        let (mut cpu, log) = logging_cpu(&[0x9E, 0xF0, 0x34]);
+        cpu.reg.ix = 0x1D;
+        cpu.reg.iy = 0x20;
+        cpu.step().unwrap();
+
+        // The value is X & ($34 + 1) and it replaces the high byte of the crossed address
+        assert_eq!(log.borrow().last(), Some(&(0x1510, 0x15, true)));
+
+        // SHX $3400,Y
+        let (mut cpu, log) = logging_cpu(&[0x9E, 0x00, 0x34]);
+        cpu.reg.ix = 0x1D;
+        cpu.reg.iy = 0x20;
+        cpu.step().unwrap();
+        assert_eq!(log.borrow().last(), Some(&(0x3420, 0x15, true)));
+    }
+
+    #[test]
+    fn arr_test() {
+        // ARR #$FF
+        let (mut cpu, _) = logging_cpu(&[0x6B, 0xFF]);
+        cpu.reg.ac = 0xC0;
+        cpu.reg.ps.insert(CPUFLAGS::CARRY);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0xE0);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::CARRY));
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+        assert!(cpu.reg.ps.contains(CPUFLAGS::NEGATIVE));
+    }
+
+    #[test]
+    fn magic_constant_test() {
+        // LXA #$5A
+        let (mut cpu, _) = logging_cpu(&[0xAB, 0x5A]);
+        cpu.set_magic_constant(0xFF);
+        cpu.reg.ac = 0x00;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x5A);
+        assert_eq!(cpu.reg.ix, 0x5A);
+
+        let (mut cpu, _) = logging_cpu(&[0xAB, 0x5A]);
+        cpu.set_magic_constant(0x00);
+        cpu.reg.ac = 0x0F;
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x0A);
+        assert_eq!(cpu.reg.ix, 0x0A);
+    }
+
+    #[test]
+    fn jam_test() {
+        // JAM
+        let (mut cpu, _) = logging_cpu(&[0x02, 0xEA]);
+        assert_eq!(
+            cpu.step(),
+            Err(CpuError::Jammed {
+                address: PROGRAM_START
+            })
+        );
+        assert!(cpu.is_jammed());
+
+        let pc = cpu.reg.pc;
+        assert!(cpu.step().is_err());
+        assert_eq!(cpu.reg.pc, pc);
+
+        cpu.interrupt(InterruptType::Reset).unwrap();
+        assert!(!cpu.is_jammed());
+    }
+
+    #[test]
+    fn disabled_undocumented_opcodes_test() {
+        // NOP; LAX $10
+        let (mut cpu, _) = logging_cpu(&[0xEA, 0xA7, 0x10]);
+        cpu.set_undocumented_opcodes(false);
+        assert_eq!(cpu.step(), Ok(StepOutcome::Instruction(2)));
+        assert_eq!(
+            cpu.step(),
+            Err(CpuError::IllegalOpcode {
+                address: PROGRAM_START + 1,
+                opcode: 0xA7
+            })
+        );
+
+        // The opcode isn't executed and PC stays on it
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+        assert_eq!(cpu.reg.ac, 0x00);
+
+        // The JAM opcodes too
+        let (mut cpu, _) = logging_cpu(&[0x02]);
+        cpu.set_undocumented_opcodes(false);
+        assert!(matches!(
+            cpu.step(),
+            Err(CpuError::IllegalOpcode { opcode: 0x02, .. })
+        ));
+        assert!(!cpu.is_jammed());
+
+        let documented = (0..=0xFF_u8)
+            .filter(|opcode| opcode_modes::is_documented(*opcode, CpuVariant::Nmos6502))
+            .count();
+        assert_eq!(documented, 151);
+    }
+}
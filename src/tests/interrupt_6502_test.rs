@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{self, AddressBus, MemoryBank};
+    use crate::cpu::{BoxedMOS6502, InterruptType, CPUFLAGS, IRQ_VECTOR, NMI_VECTOR};
+    use crate::tests::load_program;
+    use std::cell::Cell;
+    use std::fs::{self, File};
+    use std::rc::Rc;
+
+    // Follows Klaus Dormann's 6502_interrupt_test: the program drives its own IRQ and NMI
+    // lines through a feedback port, bit 0 is IRQ and bit 1 is NMI
+    const FEEDBACK_PORT: u16 = 0xBFFC;
+    const PROGRAM_START: u16 = 0x400;
+    const TEST_END_PC: u16 = 0x42F;
+    const HIJACKED_BRK: u16 = 0x428;
+    const DELAYING_BRANCH: u16 = 0x42B;
+    const IRQ_HANDLER: u16 = 0x3000;
+    const NMI_HANDLER: u16 = 0x4000;
+
+    #[rustfmt::skip]
+    const PROGRAM: [u8; 50] = [
+        // IRQ integrity
+        0xA2, 0xFF,       // 0400 LDX #$FF
+        0x9A,             // 0402 TXS
+        0x58,             // 0403 CLI
+        0xA9, 0x01,       // 0404 LDA #$01
+        0x8D, 0xFC, 0xBF, // 0406 STA $BFFC
+        0xEA,             // 0409 NOP          ; IRQ, returns to $040A
+        0xEA,             // 040A NOP
+
+        // BRK integrity
+        0x00, 0xFF,       // 040B BRK $FF      ; returns to $040D with B set
+
+        // I flag latency
+        0x78,             // 040D SEI
+        0xEA,             // 040E NOP
+        0xA9, 0x01,       // 040F LDA #$01
+        0x8D, 0xFC, 0xBF, // 0411 STA $BFFC
+        0xEA,             // 0414 NOP          ; masked
+        0x58,             // 0415 CLI
+        0xEA,             // 0416 NOP          ; IRQ, returns to $0417
+        0xEA,             // 0417 NOP
+
+        // NMI integrity
+        0x78,             // 0418 SEI
+        0xA9, 0x02,       // 0419 LDA #$02
+        0x8D, 0xFC, 0xBF, // 041B STA $BFFC
+        0xEA,             // 041E NOP          ; NMI, returns to $041F
+        0xEA,             // 041F NOP
+
+        // IRQ and NMI at the same time
+        0x58,             // 0420 CLI
+        0xA9, 0x03,       // 0421 LDA #$03
+        0x8D, 0xFC, 0xBF, // 0423 STA $BFFC
+        0xEA,             // 0426 NOP          ; NMI then IRQ, both return to $0427
+        0xEA,             // 0427 NOP
+
+        // NMI hijacking a BRK, the harness raises NMI while the return address is pushed
+        0x00, 0xFF,       // 0428 BRK $FF      ; NMI with B set, returns to $042A
+
+        // Taken branch delaying an IRQ, the harness raises it during the operand fetch
+        0x58,             // 042A CLI
+        0xD0, 0x00,       // 042B BNE *+2
+        0xEA,             // 042D NOP          ; still runs before the IRQ
+        0xEA,             // 042E NOP          ; IRQ, returns to $042E
+        0x4C, 0x2F, 0x04, // 042F JMP $042F
+    ];
+
+    // Both handlers release their line and return
+    #[rustfmt::skip]
+    const IRQ_HANDLER_PROGRAM: [u8; 11] = [
+        0x48,             // PHA
+        0xAD, 0xFC, 0xBF, // LDA $BFFC
+        0x29, 0xFE,       // AND #$FE
+        0x8D, 0xFC, 0xBF, // STA $BFFC
+        0x68,             // PLA
+        0x40,             // RTI
+    ];
+
+    #[rustfmt::skip]
+    const NMI_HANDLER_PROGRAM: [u8; 11] = [
+        0x48,             // PHA
+        0xAD, 0xFC, 0xBF, // LDA $BFFC
+        0x29, 0xFD,       // AND #$FD
+        0x8D, 0xFC, 0xBF, // STA $BFFC
+        0x68,             // PLA
+        0x40,             // RTI
+    ];
+
+    struct FeedbackBus<M: AddressBus> {
+        memory: M,
+        port: Rc<Cell<u8>>,
+    }
+
+    impl<M: AddressBus> AddressBus for FeedbackBus<M> {
+        fn read(&mut self, address: u16) -> u8 {
+            if address == FEEDBACK_PORT {
+                return self.port.get();
+            }
+            self.memory.read(address)
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            if address == FEEDBACK_PORT {
+                self.port.set(value);
+                return;
+            }
+            self.memory.write(address, value);
+        }
+    }
+
+    #[test]
+    fn interrupt_test() {
+        let port = Rc::new(Cell::new(0));
+        let bus = FeedbackBus {
+            memory: MemoryBank::new(),
+            port: port.clone(),
+        };
+        let mut cpu = BoxedMOS6502::new(Box::new(bus));
+        load_program(&mut cpu, IRQ_HANDLER, &IRQ_HANDLER_PROGRAM);
+        load_program(&mut cpu, NMI_HANDLER, &NMI_HANDLER_PROGRAM);
+        load_program(&mut cpu, IRQ_VECTOR, &IRQ_HANDLER.to_le_bytes());
+        load_program(&mut cpu, NMI_VECTOR, &NMI_HANDLER.to_le_bytes());
+        // Last so the PC ends up at its start
+        load_program(&mut cpu, PROGRAM_START, &PROGRAM);
+
+        // Every handler entry as (handler, return address, pushed status)
+        let mut entries = Vec::new();
+        while !cpu.is_trapped() {
+            // The port bit keeps the line up after the scheduled cycle until the handler
+            // releases it
+            if cpu.reg.pc == HIJACKED_BRK {
+                cpu.schedule_nmi(cpu.cycles() + 3, true);
+                port.set(port.get() | 0x02);
+            }
+            if cpu.reg.pc == DELAYING_BRANCH {
+                cpu.schedule_irq(cpu.cycles() + 1, true);
+                port.set(port.get() | 0x01);
+            }
+
+            cpu.step().unwrap();
+            cpu.set_irq(port.get() & 0x01 != 0);
+            cpu.set_nmi(port.get() & 0x02 != 0);
+
+            if cpu.reg.pc == IRQ_HANDLER || cpu.reg.pc == NMI_HANDLER {
+                let stack = 0x100 | cpu.reg.sp as u16;
+                let status = CPUFLAGS::from_bits_truncate(cpu.bus.read(stack + 1));
+                let low = cpu.bus.read(stack + 2);
+                let high = cpu.bus.read(stack + 3);
+                entries.push((
+                    cpu.reg.pc,
+                    u16::from_le_bytes([low, high]),
+                    status.contains(CPUFLAGS::BREAK),
+                ));
+            }
+        }
+
+        assert_eq!(cpu.reg.pc, TEST_END_PC);
+        assert_eq!(
+            entries,
+            vec![
+                (IRQ_HANDLER, 0x040A, false),
+                (IRQ_HANDLER, 0x040D, true),
+                (IRQ_HANDLER, 0x0417, false),
+                (NMI_HANDLER, 0x041F, false),
+                (NMI_HANDLER, 0x0427, false),
+                (IRQ_HANDLER, 0x0427, false),
+                (NMI_HANDLER, 0x042A, true),
+                (IRQ_HANDLER, 0x042E, false),
+            ]
+        );
+        assert_eq!(port.get(), 0x00);
+    }
+
+    // The real test from https://github.com/Klaus2m5/6502_65C02_functional_tests, its
+    // bin_files/6502_interrupt_test.bin and .lst copied into tests/. It isn't bundled yet, so
+    // this only runs with `cargo test -- --ignored` once they are there. The success trap
+    // address is taken from the listing since it moves with the assembler configuration
+    const KLAUS_TEST_PATH: &str = "tests/6502_interrupt_test.bin";
+    const KLAUS_LISTING_PATH: &str = "tests/6502_interrupt_test.lst";
+
+    // The address of the `jmp *` the success macro expands to. The listing also has the line
+    // in the macro definition, which has no address in front
+    fn success_address(listing: &str) -> u16 {
+        listing
+            .lines()
+            .filter(|line| line.contains("jmp *") && line.contains("test passed"))
+            .find_map(|line| u16::from_str_radix(line.get(..4)?, 16).ok())
+            .expect("no success trap in the listing")
+    }
+
+    #[test]
+    fn success_address_test() {
+        // The functional test's listing comes from the same assembler and success macro
+        let listing = fs::read_to_string("tests/6502_functional_test.lst").unwrap();
+        assert_eq!(success_address(&listing), 0x336D);
+    }
+
+    #[test]
+    #[ignore]
+    fn klaus_interrupt_test() {
+        let listing = fs::read_to_string(KLAUS_LISTING_PATH).unwrap();
+        let mut file = File::open(KLAUS_TEST_PATH).unwrap();
+
+        let port = Rc::new(Cell::new(0));
+        let bus = FeedbackBus {
+            memory: address_bus::memory_from_file(&mut file, false),
+            port: port.clone(),
+        };
+        let mut cpu = BoxedMOS6502::new(Box::new(bus));
+        cpu.interrupt(InterruptType::Reset).unwrap();
+        cpu.set_pc(PROGRAM_START);
+
+        // The lines follow the port after every cycle rather than every instruction
+        while !cpu.is_trapped() {
+            cpu.tick_cycle().unwrap();
+            cpu.set_irq(port.get() & 0x01 != 0);
+            cpu.set_nmi(port.get() & 0x02 != 0);
+        }
+
+        assert_eq!(cpu.reg.pc, success_address(&listing));
+    }
+}
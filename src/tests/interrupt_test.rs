@@ -0,0 +1,300 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, StepOutcome, CPUFLAGS, IRQ_VECTOR, MOS6502, NMI_VECTOR};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+    const IRQ_HANDLER: u16 = 0x3000;
+    const NMI_HANDLER: u16 = 0x4000;
+
+    fn cpu_with_handlers(variant: CpuVariant, program: &[u8]) -> MOS6502<MemoryBank> {
+        let mut cpu = cpu_with_program(variant, PROGRAM_START, program);
+        cpu.bus.write(IRQ_VECTOR, IRQ_HANDLER as u8);
+        cpu.bus.write(IRQ_VECTOR + 1, (IRQ_HANDLER >> 8) as u8);
+        cpu.bus.write(NMI_VECTOR, NMI_HANDLER as u8);
+        cpu.bus.write(NMI_VECTOR + 1, (NMI_HANDLER >> 8) as u8);
+
+        // Both handlers are a NOP
+        cpu.bus.write(IRQ_HANDLER, 0xEA);
+        cpu.bus.write(NMI_HANDLER, 0xEA);
+
+        cpu.reg.sp = 0xFF;
+        cpu
+    }
+
+    fn pushed_status(cpu: &mut MOS6502<MemoryBank>) -> CPUFLAGS {
+        CPUFLAGS::from_bits_truncate(cpu.bus.read(0x100 | cpu.reg.sp.wrapping_add(1) as u16))
+    }
+
+    fn pushed_pc(cpu: &mut MOS6502<MemoryBank>) -> u16 {
+        let low = cpu.bus.read(0x100 | cpu.reg.sp.wrapping_add(2) as u16) as u16;
+        let high = cpu.bus.read(0x100 | cpu.reg.sp.wrapping_add(3) as u16) as u16;
+        low | (high << 8)
+    }
+
+    #[test]
+    fn irq_test() {
+        // NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+
+        // The IRQ was seen during the first NOP and is taken instead of the second one
+        assert_eq!(cpu.step(), Ok(StepOutcome::Interrupt(7)));
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 1);
+        assert!(cpu.reg.ps.contains(CPUFLAGS::INT_DISABLE));
+
+        let status = pushed_status(&mut cpu);
+        assert!(!status.contains(CPUFLAGS::BREAK));
+        assert!(status.contains(CPUFLAGS::UNUSED));
+        assert!(!status.contains(CPUFLAGS::INT_DISABLE));
+    }
+
+    #[test]
+    fn masked_irq_test() {
+        // NOP; NOP; CLI; NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA, 0xEA, 0x58, 0xEA, 0xEA]);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+
+        // CLI only takes effect after the next instruction
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 4);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+    }
+
+    #[test]
+    fn released_irq_test() {
+        // NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+
+        // The IRQ line is level triggered, it is never seen if released before it is polled
+        cpu.set_irq(true);
+        cpu.set_irq(false);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn sei_latency_test() {
+        // SEI; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x78, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+
+        // The IRQ is still taken after SEI, with I set in the pushed status
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 1);
+        assert!(pushed_status(&mut cpu).contains(CPUFLAGS::INT_DISABLE));
+    }
+
+    #[test]
+    fn plp_latency_test() {
+        // PLP; NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x28, 0xEA, 0xEA]);
+        cpu.bus.write(0x100, 0x00);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+
+        cpu.step().unwrap();
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::INT_DISABLE));
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+    }
+
+    #[test]
+    fn rti_no_latency_test() {
+        // RTI; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x40, 0xEA]);
+        cpu.reg.sp = 0xFC;
+        cpu.bus.write(0x1FD, 0x00); // I clear
+        cpu.bus.write(0x1FE, 0x01);
+        cpu.bus.write(0x1FF, 0x02);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+
+        // RTI restores I early enough for the IRQ to be taken right after it
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+    }
+
+    #[test]
+    fn nmi_edge_test() {
+        // NOP x 8
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA; 8]);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.bus.write(NMI_HANDLER, 0x40); // RTI
+        cpu.set_nmi(true);
+
+        // The NMI ignores I and is taken once even though the line stays asserted
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+        assert!(!pushed_status(&mut cpu).contains(CPUFLAGS::BREAK));
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+
+        // It needs to be released and asserted again
+        cpu.set_nmi(true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 4);
+        cpu.set_nmi(false);
+        cpu.set_nmi(true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+    }
+
+    #[test]
+    fn nmi_over_irq_test() {
+        // NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.set_nmi(true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+
+        // The first handler instruction runs, then the IRQ waits since I is now set
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER + 1);
+    }
+
+    #[test]
+    fn brk_pushes_break_test() {
+        // BRK
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x00, 0x00]);
+        assert_eq!(cpu.step().unwrap().cycles(), 7);
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 2);
+        assert!(pushed_status(&mut cpu).contains(CPUFLAGS::BREAK));
+    }
+
+    #[test]
+    fn brk_hijack_test() {
+        // BRK, the NMI arrives while the return address is pushed
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x00, 0x00]);
+        cpu.schedule_nmi(3, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 2);
+        assert!(pushed_status(&mut cpu).contains(CPUFLAGS::BREAK));
+
+        // The NMI was taken by the hijack and doesn't happen again
+        cpu.bus.write(NMI_HANDLER + 1, 0xEA);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER + 2);
+    }
+
+    #[test]
+    fn brk_late_nmi_test() {
+        // BRK, the NMI arrives while the status is pushed, too late to hijack
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0x00, 0x00]);
+        cpu.schedule_nmi(4, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+
+        // It is taken after the first instruction of the BRK handler
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), IRQ_HANDLER + 1);
+    }
+
+    #[test]
+    fn irq_hijack_test() {
+        // NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+
+        // The IRQ sequence starts at cycle 2 and pushes PCH during cycle 4
+        cpu.schedule_nmi(5, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+        assert!(!pushed_status(&mut cpu).contains(CPUFLAGS::BREAK));
+
+        // The 65C02 runs the IRQ handler's first instruction and then the NMI
+        let mut cpu = cpu_with_handlers(CpuVariant::W65C02S, &[0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        cpu.schedule_nmi(5, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, NMI_HANDLER);
+    }
+
+    #[test]
+    fn branch_delays_interrupt_test() {
+        // BNE *+2; NOP; NOP
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xD0, 0x00, 0xEA, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.reg.ps.remove(CPUFLAGS::ZERO);
+
+        // The IRQ arrives during the operand fetch and the branch is taken without a page cross
+        cpu.schedule_irq(1, true);
+        assert_eq!(cpu.step().unwrap().cycles(), 3);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+
+        // So the following instruction still runs before the interrupt
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 3);
+    }
+
+    #[test]
+    fn branch_interrupt_test() {
+        // BNE *+2; NOP, the IRQ arrives during the opcode fetch which is not delayed
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[0xD0, 0x00, 0xEA]);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.reg.ps.remove(CPUFLAGS::ZERO);
+        cpu.schedule_irq(0, true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), PROGRAM_START + 2);
+
+        // A branch crossing a page polls again during its last cycle
+        let mut cpu = cpu_with_handlers(CpuVariant::Nmos6502, &[]);
+        cpu.bus.write(0x2FD, 0xD0); // BNE $0300
+        cpu.bus.write(0x2FE, 0x01);
+        cpu.set_pc(0x2FD);
+        cpu.reg.ps.remove(CPUFLAGS::INT_DISABLE);
+        cpu.reg.ps.remove(CPUFLAGS::ZERO);
+        cpu.schedule_irq(1, true);
+        assert_eq!(cpu.step().unwrap().cycles(), 4);
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.pc, IRQ_HANDLER);
+        assert_eq!(pushed_pc(&mut cpu), 0x300);
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, MOS6502};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    // Returns the CPU after the jump and the cycles it took
+    fn jmp_indirect(variant: CpuVariant, vector: u16) -> (MOS6502<MemoryBank>, u64) {
+        // JMP ($vector)
+        let program = [0x6C, vector as u8, (vector >> 8) as u8];
+        let mut cpu = cpu_with_program(variant, PROGRAM_START, &program);
+
+        // The high byte at the start of the vector's page differs from the one in the next page
+        cpu.bus.write(vector, 0x34);
+        cpu.bus.write(vector.wrapping_add(1), 0x12);
+        cpu.bus.write(vector & 0xFF00, 0x56);
+
+        let cycles = cpu.step().unwrap().cycles();
+        (cpu, cycles)
+    }
+
+    #[test]
+    fn jmp_indirect_test() {
+        let (cpu, cycles) = jmp_indirect(CpuVariant::Nmos6502, 0x3080);
+        assert_eq!(cpu.reg.pc, 0x1234);
+        assert_eq!(cycles, 5);
+
+        // The 65C02 spends a cycle more, with or without crossing a page
+        let (cpu, cycles) = jmp_indirect(CpuVariant::W65C02S, 0x3080);
+        assert_eq!(cpu.reg.pc, 0x1234);
+        assert_eq!(cycles, 6);
+    }
+
+    #[test]
+    fn jmp_indirect_page_wrap_test() {
+        for variant in [CpuVariant::Nmos6502, CpuVariant::Ricoh2A03] {
+            let (cpu, cycles) = jmp_indirect(variant, 0x30FF);
+            assert_eq!(cpu.reg.pc, 0x5634);
+            assert_eq!(cycles, 5);
+
+            let (cpu, cycles) = jmp_indirect(variant, 0xFFFF);
+            assert_eq!(cpu.reg.pc, 0x5634);
+            assert_eq!(cycles, 5);
+        }
+    }
+
+    #[test]
+    fn cmos_jmp_indirect_page_wrap_test() {
+        for variant in [
+            CpuVariant::Cmos65SC02,
+            CpuVariant::Cmos65C02,
+            CpuVariant::W65C02S,
+        ] {
+            let (cpu, cycles) = jmp_indirect(variant, 0x30FF);
+            assert_eq!(cpu.reg.pc, 0x1234);
+            assert_eq!(cycles, 6);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{CpuVariant, RewindPolicy, CPUFLAGS, IRQ_VECTOR, STACK_BASE};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    #[test]
+    fn last_write_test() {
+        // LDA #$11, STA $10, INC $10, NOP
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xA9, 0x11, 0x85, 0x10, 0xE6, 0x10, 0xEA],
+        );
+        cpu.set_write_tracking(Some(1));
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+
+        // INC writes twice, the unmodified value first
+        let write = cpu.last_write(0x10).unwrap();
+        assert_eq!(write.instruction_address, PROGRAM_START + 4);
+        assert_eq!(write.opcode, Some(0xE6));
+        assert_eq!(write.value, 0x12);
+        assert_eq!(write.cycle, 2 + 3 + 4);
+        assert_eq!(cpu.write_history(0x10).count(), 1);
+
+        assert_eq!(cpu.last_write(0x11), None);
+        assert_eq!(cpu.last_write(PROGRAM_START), None);
+    }
+
+    #[test]
+    fn write_history_test() {
+        // STA $10, STX $10, STY $10, INC $10
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0x85, 0x10, 0x86, 0x10, 0x84, 0x10, 0xE6, 0x10],
+        );
+        cpu.set_write_tracking(Some(3));
+        cpu.reg.ac = 1;
+        cpu.reg.ix = 2;
+        cpu.reg.iy = 3;
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+
+        let history: Vec<(u16, u8)> = cpu
+            .write_history(0x10)
+            .map(|write| (write.instruction_address, write.value))
+            .collect();
+        let expected = vec![
+            (PROGRAM_START + 6, 4),
+            (PROGRAM_START + 6, 3),
+            (PROGRAM_START + 4, 3),
+        ];
+        assert_eq!(history, expected);
+
+        cpu.set_write_tracking(None);
+        assert_eq!(cpu.last_write(0x10), None);
+    }
+
+    #[test]
+    fn interrupt_write_test() {
+        // CLI, NOP
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0x58, 0xEA]);
+        cpu.bus.write(IRQ_VECTOR, 0x00);
+        cpu.bus.write(IRQ_VECTOR + 1, 0x04);
+        cpu.set_write_tracking(Some(1));
+        cpu.step().unwrap();
+        cpu.set_irq(true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        // The status pushed by the IRQ sequence
+        let write = cpu.last_write(STACK_BASE + cpu.reg.sp as u16 + 1).unwrap();
+        assert_eq!(write.instruction_address, PROGRAM_START + 2);
+        assert_eq!(write.opcode, None);
+        assert!(write.value & CPUFLAGS::BREAK.bits() == 0);
+    }
+
+    #[test]
+    fn rewound_write_test() {
+        // STA $10, STX $10, NOP
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0x85, 0x10, 0x86, 0x10, 0xEA],
+        );
+        cpu.set_write_tracking(Some(4));
+        cpu.set_rewind(Some(RewindPolicy::default()));
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        // Writes undone by rewinding are forgotten, not tracked twice
+        cpu.step_back().unwrap();
+        let write = cpu.last_write(0x10).unwrap();
+        assert_eq!(write.instruction_address, PROGRAM_START);
+        assert_eq!(cpu.write_history(0x10).count(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{BoxedMOS6502, CpuError, InterruptType};
+    use crate::tests::load_program;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const PROGRAM_START: u16 = 0x200;
+    const DMA_PORT: u16 = 0x4014;
+    const DMA_CYCLES: u64 = 513;
+
+    #[derive(Default)]
+    struct DmaState {
+        remaining: u64,
+        halted_at: Vec<u64>, // Every cycle the CPU was halted during
+    }
+
+    // Like the NES OAM DMA, writing to the port steals the CPU for a fixed number of cycles
+    struct DmaBus {
+        memory: MemoryBank,
+        dma: Rc<RefCell<DmaState>>,
+    }
+
+    impl AddressBus for DmaBus {
+        fn read(&mut self, address: u16) -> u8 {
+            self.memory.read(address)
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            if address == DMA_PORT {
+                self.dma.borrow_mut().remaining = DMA_CYCLES;
+            }
+            self.memory.write(address, value);
+        }
+
+        fn rdy(&mut self, cycle: u64) -> bool {
+            let mut dma = self.dma.borrow_mut();
+            if dma.remaining == 0 {
+                return true;
+            }
+            dma.remaining -= 1;
+            dma.halted_at.push(cycle);
+            false
+        }
+    }
+
+    fn dma_cpu(program: &[u8]) -> (BoxedMOS6502, Rc<RefCell<DmaState>>) {
+        let dma = Rc::new(RefCell::new(DmaState::default()));
+        let bus = DmaBus {
+            memory: MemoryBank::new(),
+            dma: dma.clone(),
+        };
+        let mut cpu = BoxedMOS6502::new(Box::new(bus));
+        load_program(&mut cpu, PROGRAM_START, program);
+        (cpu, dma)
+    }
+
+    #[test]
+    fn stall_test() {
+        // NOP; NOP
+        let (mut cpu, _) = dma_cpu(&[0xEA, 0xEA]);
+        cpu.step().unwrap();
+        cpu.stall(10);
+        assert_eq!(cpu.step().unwrap().cycles(), 12);
+        assert_eq!(cpu.halted_at(), Some(2));
+        assert_eq!(cpu.halted_cycles(), 10);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+    }
+
+    #[test]
+    fn dma_test() {
+        // STA $4014; NOP
+        let (mut cpu, dma) = dma_cpu(&[0x8D, 0x14, 0x40, 0xEA]);
+        assert_eq!(cpu.step().unwrap().cycles(), 4);
+
+        // The CPU stops at the opcode fetch of the NOP
+        assert_eq!(cpu.step().unwrap().cycles(), 2 + DMA_CYCLES);
+        assert_eq!(cpu.halted_at(), Some(4));
+        assert_eq!(dma.borrow().halted_at.first(), Some(&4));
+        assert_eq!(dma.borrow().halted_at.last(), Some(&(4 + DMA_CYCLES - 1)));
+        assert_eq!(cpu.cycles(), 6 + DMA_CYCLES);
+    }
+
+    #[test]
+    fn rdy_ignored_on_write_test() {
+        // INC $4014; NOP
+        let (mut cpu, dma) = dma_cpu(&[0xEE, 0x14, 0x40, 0xEA]);
+
+        // RDY goes low on the first write of the read-modify-write but the second write
+        // still happens, the CPU only stops at the following read
+        assert_eq!(cpu.step().unwrap().cycles(), 6);
+        assert_eq!(cpu.bus.read(DMA_PORT), 0x01);
+        assert_eq!(cpu.halted_at(), None);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.halted_at(), Some(6));
+        assert_eq!(dma.borrow().halted_at.len() as u64, DMA_CYCLES);
+    }
+
+    #[test]
+    fn halted_limit_test() {
+        // NOP; NOP
+        let (mut cpu, dma) = dma_cpu(&[0xEA, 0xEA]);
+        cpu.set_max_halted_cycles(100);
+        dma.borrow_mut().remaining = 250;
+
+        // step() gives up after the limit and picks the NOP up again on the next call
+        let halted = Err(CpuError::Halted {
+            address: PROGRAM_START,
+        });
+        assert_eq!(cpu.step(), halted);
+        assert_eq!(cpu.step(), halted);
+        assert_eq!(cpu.step().unwrap().cycles(), 2 + 250);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+    }
+
+    #[test]
+    fn halted_forever_test() {
+        // NOP
+        let (mut cpu, dma) = dma_cpu(&[0xEA]);
+        cpu.set_max_halted_cycles(100);
+        dma.borrow_mut().remaining = u64::MAX;
+
+        assert!(matches!(cpu.step(), Err(CpuError::Halted { .. })));
+        assert!(matches!(
+            cpu.interrupt(InterruptType::Nmi),
+            Err(CpuError::Halted { .. })
+        ));
+        assert_eq!(cpu.cycles(), 202);
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{self, AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, InterruptType, RewindError, RewindPolicy, MOS6502};
+    use crate::tests::{cpu_with_program, load_program};
+    use std::fs::File;
+
+    const TEST_FILE_PATH: &str = "tests/6502_functional_test.bin";
+    const TEST_START_PC: u16 = 0x400;
+    const PROGRAM_START: u16 = 0x200;
+
+    fn rewinding_cpu(program: &[u8]) -> MOS6502<MemoryBank> {
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, program);
+        cpu.set_rewind(Some(RewindPolicy::default()));
+        cpu
+    }
+
+    // Every read of $D000 counts up, without a save state it can't be rewound
+    struct CounterBus {
+        memory: MemoryBank,
+        counter: u8,
+    }
+
+    impl AddressBus for CounterBus {
+        fn read(&mut self, address: u16) -> u8 {
+            match address {
+                0xD000 => {
+                    self.counter = self.counter.wrapping_add(1);
+                    self.counter
+                }
+                _ => self.memory.read(address),
+            }
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            self.memory.write(address, value);
+        }
+    }
+
+    #[test]
+    fn step_back_test() {
+        // LDA #$11, STA $10, INC $10, INX
+        let mut cpu = rewinding_cpu(&[0xA9, 0x11, 0x85, 0x10, 0xE6, 0x10, 0xE8]);
+        for _ in 0..4 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.bus.read(0x10), 0x12);
+
+        cpu.step_back().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 6);
+        assert_eq!(cpu.reg.ix, 0);
+        assert_eq!(cpu.cycles(), 2 + 3 + 5);
+
+        cpu.step_back().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 4);
+        assert_eq!(cpu.bus.read(0x10), 0x11);
+
+        cpu.step_back().unwrap();
+        cpu.step_back().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START);
+        assert_eq!(cpu.bus.read(0x10), 0x00);
+        assert_eq!(cpu.step_back(), Err(RewindError::NotFound));
+
+        // Running forward again journals the instructions anew
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step_back().unwrap();
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 2);
+        assert_eq!(cpu.reg.ac, 0x11);
+    }
+
+    #[test]
+    fn rewind_cycles_test() {
+        let mut file = File::open(TEST_FILE_PATH).unwrap();
+        let mut cpu = MOS6502::new(address_bus::memory_from_file(&mut file, false));
+        cpu.interrupt(InterruptType::Reset).unwrap();
+        cpu.set_pc(TEST_START_PC);
+        cpu.set_rewind(Some(RewindPolicy {
+            snapshot_interval: 1000,
+            history: 50_000,
+        }));
+
+        // Saved in the middle of an instruction, rewinding can land there too
+        for _ in 0..20_000 {
+            cpu.tick_cycle().unwrap();
+        }
+        let cycles = cpu.cycles();
+        let expected = cpu.save_state();
+        for _ in 0..30_001 {
+            cpu.tick_cycle().unwrap();
+        }
+
+        cpu.rewind_cycles(30_001).unwrap();
+        assert_eq!(cpu.cycles(), cycles);
+        assert_eq!(cpu.save_state(), expected);
+
+        // Only the most recent cycles are kept
+        for _ in 0..100_000 {
+            cpu.tick_cycle().unwrap();
+        }
+        assert_eq!(cpu.rewind_to(cycles), Err(RewindError::OutOfHistory));
+        assert_eq!(
+            cpu.rewind_to(cpu.cycles() + 1),
+            Err(RewindError::OutOfHistory)
+        );
+        cpu.rewind_cycles(50_000).unwrap();
+    }
+
+    #[test]
+    fn run_back_to_test() {
+        // LDX #$03, loop: DEX, BNE loop, NOP
+        let mut cpu = rewinding_cpu(&[0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0xEA]);
+        for _ in 0..8 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 6);
+
+        // The last time round the loop
+        cpu.run_back_to(PROGRAM_START + 2).unwrap();
+        assert_eq!(cpu.reg.ix, 1);
+        cpu.run_back_to(PROGRAM_START + 2).unwrap();
+        assert_eq!(cpu.reg.ix, 2);
+        assert_eq!(cpu.run_back_to(0x1234), Err(RewindError::NotFound));
+    }
+
+    #[test]
+    fn run_back_to_write_test() {
+        // LDA #$11, STA $10, LDA #$22, STA $11, LDA #$33, STA $10, NOP
+        let program = [
+            0xA9, 0x11, 0x85, 0x10, 0xA9, 0x22, 0x85, 0x11, 0xA9, 0x33, 0x85, 0x10, 0xEA,
+        ];
+        let mut cpu = rewinding_cpu(&program);
+        for _ in 0..7 {
+            cpu.step().unwrap();
+        }
+
+        let write = cpu.run_back_to_write(0x10).unwrap();
+        assert_eq!(write.instruction_address, PROGRAM_START + 10);
+        assert_eq!(write.value, 0x33);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 10);
+        assert_eq!(cpu.bus.read(0x10), 0x11);
+
+        let write = cpu.run_back_to_write(0x10).unwrap();
+        assert_eq!(write.instruction_address, PROGRAM_START + 2);
+        assert_eq!(cpu.bus.read(0x10), 0x00);
+        assert_eq!(cpu.run_back_to_write(0x10), Err(RewindError::NotFound));
+    }
+
+    #[test]
+    fn diverged_test() {
+        // loop: LDA $D000, STA $10, JMP loop
+        let memory = MemoryBank::new();
+        let mut cpu = MOS6502::new(CounterBus { memory, counter: 0 });
+        let program = [0xAD, 0x00, 0xD0, 0x85, 0x10, 0x4C, 0x00, 0x02];
+        load_program(&mut cpu, PROGRAM_START, &program);
+        assert_eq!(cpu.step_back(), Err(RewindError::Disabled));
+
+        cpu.set_rewind(Some(RewindPolicy::default()));
+        for _ in 0..6 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.rewind_writes().count(), 2);
+
+        // The counter doesn't go back so the replay stores another value, which is caught
+        // and leaves the CPU where it was
+        let cycles = cpu.cycles();
+        assert_eq!(cpu.step_back(), Err(RewindError::Diverged { cycle: 6 }));
+        assert_eq!(cpu.cycles(), cycles);
+        assert_eq!(cpu.reg.ac, 2);
+    }
+}
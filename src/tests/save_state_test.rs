@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{self, AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, InterruptType, MOS6502};
+    use crate::save_state::{StateError, FORMAT_VERSION, MAGIC};
+    use crate::tests::cpu_with_program;
+    use std::fs::File;
+
+    const TEST_FILE_PATH: &str = "tests/6502_functional_test.bin";
+    const TEST_START_PC: u16 = 0x400;
+    const PROGRAM_START: u16 = 0x200;
+
+    // Saved by the first release of the format three cycles into a JSR $0300 at $0200, with A,
+    // X and Y set to $11, $22 and $33 and an NMI scheduled for cycle 100
+    const V1_STATE_PATH: &str = "tests/save_state_v1.bin";
+
+    // A device with a register that isn't visible through memory
+    struct LatchBus {
+        memory: MemoryBank,
+        latch: u8,
+    }
+
+    impl AddressBus for LatchBus {
+        fn read(&mut self, address: u16) -> u8 {
+            match address {
+                0xD000 => self.latch,
+                _ => self.memory.read(address),
+            }
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            match address {
+                0xD000 => self.latch = value ^ 0xFF,
+                _ => self.memory.write(address, value),
+            }
+        }
+
+        fn save_state(&self, state: &mut Vec<u8>) {
+            state.push(self.latch);
+            self.memory.save_state(state);
+        }
+
+        fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+            let (&latch, memory) = state.split_first().ok_or(StateError::Truncated)?;
+            self.memory.load_state(memory)?;
+            self.latch = latch;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let mut file = File::open(TEST_FILE_PATH).unwrap();
+        let mut cpu = MOS6502::new(address_bus::memory_from_file(&mut file, false));
+        cpu.interrupt(InterruptType::Reset).unwrap();
+        cpu.set_pc(TEST_START_PC);
+        for _ in 0..10_000 {
+            cpu.step().unwrap();
+        }
+
+        let state = cpu.save_state();
+        for _ in 0..10_000 {
+            cpu.step().unwrap();
+        }
+        let expected = cpu.save_state();
+
+        // Running the same instructions again from the loaded state ends up in the same place,
+        // memory included
+        cpu.load_state(&state).unwrap();
+        assert_eq!(cpu.save_state(), state);
+        for _ in 0..10_000 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.save_state(), expected);
+    }
+
+    #[test]
+    fn mid_instruction_test() {
+        // JSR $0300
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0x20, 0x00, 0x03]);
+        cpu.tick_cycle().unwrap();
+        cpu.tick_cycle().unwrap();
+        cpu.tick_cycle().unwrap();
+        let state = cpu.save_state();
+
+        let mut other = MOS6502::with_variant(MemoryBank::new(), CpuVariant::Cmos65C02);
+        other.load_state(&state).unwrap();
+        assert!(other.mid_instruction());
+        other.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(other.reg.pc, 0x300);
+        assert_eq!(other.cycles(), 6);
+        assert_eq!(other.save_state(), cpu.save_state());
+    }
+
+    #[test]
+    fn pending_interrupt_test() {
+        // CLI, NOP, NOP
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0x58, 0xEA, 0xEA]);
+        cpu.bus.write(0xFFFE, 0x00);
+        cpu.bus.write(0xFFFF, 0x04);
+        cpu.step().unwrap();
+        cpu.set_irq(true);
+        cpu.schedule_nmi(100, true);
+        let state = cpu.save_state();
+
+        let mut other = MOS6502::new(MemoryBank::new());
+        other.load_state(&state).unwrap();
+
+        // The IRQ asserted before saving is still taken after the next instruction
+        for cpu in [&mut cpu, &mut other] {
+            cpu.step().unwrap();
+            cpu.step().unwrap();
+        }
+        assert_eq!(other.reg.pc, 0x400);
+        assert_eq!(other.save_state(), cpu.save_state());
+    }
+
+    #[test]
+    fn device_state_test() {
+        // STA $D000
+        let memory = MemoryBank::new();
+        let mut cpu = MOS6502::new(LatchBus { memory, latch: 0 });
+        cpu.bus.write(PROGRAM_START, 0x8D);
+        cpu.bus.write(PROGRAM_START + 1, 0x00);
+        cpu.bus.write(PROGRAM_START + 2, 0xD0);
+        cpu.set_pc(PROGRAM_START);
+        cpu.reg.ac = 0x0F;
+        cpu.step().unwrap();
+        let state = cpu.save_state();
+
+        cpu.bus.latch = 0;
+        cpu.load_state(&state).unwrap();
+        assert_eq!(cpu.bus.latch, 0xF0);
+        assert_eq!(cpu.bus.read(PROGRAM_START), 0x8D);
+    }
+
+    #[test]
+    fn header_test() {
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA]);
+        cpu.reg.ac = 0x42;
+        let state = cpu.save_state();
+
+        // Magic, version, variant, PC then SP and A
+        assert_eq!(&state[..4], MAGIC);
+        assert_eq!(state[4..6], FORMAT_VERSION.to_le_bytes());
+        assert_eq!(state[6], 0);
+        assert_eq!(state[7..9], PROGRAM_START.to_le_bytes());
+        assert_eq!(state[10], 0x42);
+
+        // The bus comes last, a whole memory bank
+        let bus_length = state.len() - 0x10000;
+        assert_eq!(state[bus_length - 4..bus_length], 0x10000_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn invalid_state_test() {
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA]);
+        let state = cpu.save_state();
+
+        assert_eq!(cpu.load_state(b"NES\x1A"), Err(StateError::NotASaveState));
+
+        let mut newer = state.clone();
+        newer[4..6].copy_from_slice(&99_u16.to_le_bytes());
+        assert_eq!(
+            cpu.load_state(&newer),
+            Err(StateError::UnsupportedVersion(99))
+        );
+
+        assert_eq!(cpu.load_state(&state[..40]), Err(StateError::Truncated));
+
+        let mut variant = state.clone();
+        variant[6] = 9;
+        assert_eq!(
+            cpu.load_state(&variant),
+            Err(StateError::Invalid("variant"))
+        );
+
+        let mut trailing = state.clone();
+        trailing.push(0);
+        assert!(cpu.load_state(&trailing).is_err());
+
+        // A failed load leaves everything as it was
+        cpu.reg.ac = 0x42;
+        let before = cpu.save_state();
+        let mut short_bus = state.clone();
+        short_bus.pop();
+        assert!(cpu.load_state(&short_bus).is_err());
+        assert_eq!(cpu.save_state(), before);
+    }
+
+    #[test]
+    fn invalid_instruction_cycle_test() {
+        // JSR $0300, saved after its opcode fetch
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0x20, 0x00, 0x03]);
+        cpu.tick_cycle().unwrap();
+        let state = cpu.save_state();
+
+        // The instruction cycle comes before the sequence start, the addresses, the data and
+        // the two flags, then the bus
+        let cycle = state.len() - 0x10000 - 4 - 17 - 1;
+        assert_eq!(state[cycle - 2..=cycle], [2, 0x20, 1]);
+
+        let before = cpu.save_state();
+        for corrupted in [0, 8, 0xFF] {
+            let mut invalid = state.clone();
+            invalid[cycle] = corrupted;
+            assert_eq!(
+                cpu.load_state(&invalid),
+                Err(StateError::Invalid("instruction cycle"))
+            );
+            assert_eq!(cpu.save_state(), before);
+        }
+
+        // Between instructions nothing has run yet
+        let mut idle = state.clone();
+        idle[cycle - 2] = 0;
+        assert_eq!(
+            cpu.load_state(&idle),
+            Err(StateError::Invalid("instruction cycle"))
+        );
+
+        // Resuming at the last cycle of JSR still loads
+        let mut last = state.clone();
+        last[cycle] = 5;
+        cpu.load_state(&last).unwrap();
+    }
+
+    #[test]
+    fn version_1_test() {
+        let state = std::fs::read(V1_STATE_PATH).unwrap();
+        assert_eq!(state[4..6], 1_u16.to_le_bytes());
+
+        let mut cpu = MOS6502::with_variant(MemoryBank::new(), CpuVariant::W65C02S);
+        cpu.load_state(&state).unwrap();
+        assert_eq!(cpu.variant(), CpuVariant::Nmos6502);
+        assert!(cpu.mid_instruction());
+        assert_eq!(cpu.cycles(), 3);
+        assert_eq!((cpu.reg.ac, cpu.reg.ix, cpu.reg.iy), (0x11, 0x22, 0x33));
+
+        // The JSR finishes and the RTS returns
+        assert_eq!(cpu.step().unwrap().cycles(), 6);
+        assert_eq!(cpu.reg.pc, 0x300);
+        assert_eq!(cpu.step().unwrap().cycles(), 6);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+        assert_eq!(cpu.bus.read(0x1FD), 0x02);
+        assert_eq!(cpu.bus.read(0x1FC), 0x02);
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::cpu::{CpuVariant, CPUFLAGS};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    #[test]
+    fn byte_ready_loop_test() {
+        // CLV; BVC *; NOP like the 1541 waiting for a byte
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xB8, 0x50, 0xFE, 0xEA],
+        );
+        cpu.reg.ps.insert(CPUFLAGS::OVERFLOW);
+        cpu.step().unwrap();
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+
+        // Each pass through the loop takes 3 cycles, the branch starting at cycle 8 has
+        // already checked V when SO is asserted during cycle 10
+        cpu.schedule_so(10, true);
+        while cpu.reg.pc != PROGRAM_START + 3 {
+            cpu.step().unwrap();
+        }
+        assert!(cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+        assert_eq!(cpu.cycles(), 13);
+    }
+
+    #[test]
+    fn set_overflow_edge_test() {
+        // NOP; CLV; NOP; NOP
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xEA, 0xB8, 0xEA, 0xEA],
+        );
+        cpu.set_so(true);
+        cpu.step().unwrap();
+        assert!(cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+
+        // Holding SO asserted doesn't set V again
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert!(!cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+
+        cpu.set_so(false);
+        cpu.set_so(true);
+        cpu.step().unwrap();
+        assert!(cpu.reg.ps.contains(CPUFLAGS::OVERFLOW));
+    }
+}
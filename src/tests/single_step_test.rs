@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{BusOperation, CpuError, CpuVariant, TrapPolicy, CPUFLAGS, MOS6502};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::panic;
+    use std::path::Path;
+
+    // Runs Tom Harte's SingleStepTests (https://github.com/SingleStepTests/65x02), one JSON
+    // file per opcode named after it ("a9.json"). The suite is too big to ship so point these
+    // variables at a local checkout and run with `cargo test -- --ignored --nocapture`
+    const NMOS_TESTS_VARIABLE: &str = "SINGLE_STEP_TESTS_6502";
+    const CMOS_TESTS_VARIABLE: &str = "SINGLE_STEP_TESTS_65C02";
+
+    // A handful of hand written cases in the same format, checks the harness itself
+    const FIXTURE_PATH: &str = "tests/single_step";
+
+    #[derive(Deserialize)]
+    struct TestState {
+        pc: u16,
+        s: u8,
+        a: u8,
+        x: u8,
+        y: u8,
+        p: u8,
+        ram: Vec<(u16, u8)>,
+    }
+
+    #[derive(Deserialize)]
+    struct TestCase {
+        name: String,
+        initial: TestState,
+        #[serde(rename = "final")]
+        result: TestState,
+        cycles: Vec<(u16, u8, String)>,
+    }
+
+    // Only the bytes a case touches, a full 64KB bank per case makes the suite crawl
+    #[derive(Default)]
+    struct SparseMemory {
+        bytes: HashMap<u16, u8>,
+    }
+
+    impl AddressBus for SparseMemory {
+        fn read(&mut self, address: u16) -> u8 {
+            self.bytes.get(&address).copied().unwrap_or(0)
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            self.bytes.insert(address, value);
+        }
+    }
+
+    // Passed and total cases of every opcode, None when there was no file for it
+    type Matrix = [Option<(usize, usize)>; 256];
+
+    fn run_case(case: &TestCase, variant: CpuVariant) -> Result<(), String> {
+        let mut memory = SparseMemory::default();
+        for &(address, value) in &case.initial.ram {
+            memory.write(address, value);
+        }
+
+        let mut cpu = MOS6502::with_variant(Box::new(memory), variant);
+        cpu.set_trap_policy(TrapPolicy {
+            self_jump: false,
+            self_branch: false,
+            jam: false,
+            stop: false,
+            ..TrapPolicy::default()
+        });
+        cpu.reg.pc = case.initial.pc;
+        cpu.reg.sp = case.initial.s;
+        cpu.reg.ac = case.initial.a;
+        cpu.reg.ix = case.initial.x;
+        cpu.reg.iy = case.initial.y;
+        cpu.reg.ps = CPUFLAGS::from_bits_retain(case.initial.p);
+        cpu.set_bus_log(true);
+
+        // A JAM or STP still leaves a state and bus activity to compare
+        match cpu.step() {
+            Ok(_) | Err(CpuError::Jammed { .. } | CpuError::Stopped { .. }) => {}
+            Err(error) => return Err(error.to_string()),
+        }
+
+        let expected = &case.result;
+        let registers = [
+            ("pc", cpu.reg.pc, expected.pc),
+            ("s", cpu.reg.sp as u16, expected.s as u16),
+            ("a", cpu.reg.ac as u16, expected.a as u16),
+            ("x", cpu.reg.ix as u16, expected.x as u16),
+            ("y", cpu.reg.iy as u16, expected.y as u16),
+            ("p", cpu.reg.ps.bits() as u16, expected.p as u16),
+        ];
+        for (name, actual, expected) in registers {
+            if actual != expected {
+                return Err(format!("{name} is ${actual:04X}, expected ${expected:04X}"));
+            }
+        }
+
+        for &(address, value) in &expected.ram {
+            let actual = cpu.bus.read(address);
+            if actual != value {
+                return Err(format!(
+                    "${address:04X} is ${actual:02X}, expected ${value:02X}"
+                ));
+            }
+        }
+
+        let accesses: Vec<(u16, u8, BusOperation)> = cpu
+            .take_bus_log()
+            .iter()
+            .map(|access| (access.address, access.data, access.operation))
+            .collect();
+        let expected_accesses: Vec<(u16, u8, BusOperation)> = case
+            .cycles
+            .iter()
+            .map(|(address, data, operation)| {
+                let operation = match operation.as_str() {
+                    "write" => BusOperation::Write,
+                    _ => BusOperation::Read,
+                };
+                (*address, *data, operation)
+            })
+            .collect();
+        if accesses != expected_accesses {
+            return Err(format!(
+                "bus activity {accesses:X?}, expected {expected_accesses:X?}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    // A panic fails the case instead of taking the rest of the suite down with it
+    fn run_case_catching(case: &TestCase, variant: CpuVariant) -> Result<(), String> {
+        panic::catch_unwind(|| run_case(case, variant)).unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("panicked: {message}"))
+        })
+    }
+
+    // Runs every opcode file in the directory, returns the matrix and the first failure of
+    // each failing opcode
+    fn run_suite(directory: &Path, variant: CpuVariant) -> (Matrix, Vec<String>) {
+        let mut matrix: Matrix = [None; 256];
+        let mut failures = Vec::new();
+
+        for opcode in 0..=0xFF_u8 {
+            let path = directory.join(format!("{opcode:02x}.json"));
+            let Ok(file) = File::open(&path) else {
+                continue;
+            };
+
+            let cases: Vec<TestCase> = serde_json::from_reader(BufReader::new(file))
+                .unwrap_or_else(|error| panic!("Failed to parse {}: {error}", path.display()));
+
+            let mut passed = 0;
+            let mut first_failure = None;
+            for case in &cases {
+                match run_case_catching(case, variant) {
+                    Ok(()) => passed += 1,
+                    Err(error) => {
+                        first_failure.get_or_insert_with(|| format!("\"{}\": {error}", case.name));
+                    }
+                }
+            }
+
+            if let Some(failure) = first_failure {
+                failures.push(format!("${opcode:02X} {failure}"));
+            }
+            matrix[opcode as usize] = Some((passed, cases.len()));
+        }
+
+        (matrix, failures)
+    }
+
+    // "ok" when every case passed, the number of failing cases otherwise and "--" when the
+    // opcode wasn't tested
+    fn format_matrix(matrix: &Matrix) -> String {
+        let mut output = String::from("    ");
+        for column in 0..16 {
+            output += &format!("   x{column:X}");
+        }
+        output += "\n";
+
+        for row in 0..16 {
+            output += &format!("  {row:X}x");
+            for column in 0..16 {
+                let cell = match matrix[row * 16 + column] {
+                    None => String::from("--"),
+                    Some((passed, total)) if passed == total => String::from("ok"),
+                    Some((passed, total)) => (total - passed).to_string(),
+                };
+                output += &format!(" {cell:>4}");
+            }
+            output += "\n";
+        }
+
+        output
+    }
+
+    fn run_suite_from_variable(variable: &str, variant: CpuVariant) {
+        let Ok(directory) = std::env::var(variable) else {
+            panic!("{variable} isn't set, point it at the SingleStepTests directory");
+        };
+
+        let (matrix, failures) = run_suite(Path::new(&directory), variant);
+        println!("{}", format_matrix(&matrix));
+        for failure in &failures {
+            println!("{failure}");
+        }
+
+        assert!(
+            matrix.iter().any(Option::is_some),
+            "No tests found in {directory}"
+        );
+        assert!(failures.is_empty(), "{} opcodes failed", failures.len());
+    }
+
+    #[test]
+    fn single_step_fixture_test() {
+        let (matrix, failures) = run_suite(Path::new(FIXTURE_PATH), CpuVariant::Nmos6502);
+        assert!(failures.is_empty(), "{failures:#?}");
+
+        let tested: Vec<usize> = (0..256).filter(|&i| matrix[i].is_some()).collect();
+        assert_eq!(tested, vec![0x02, 0x08, 0x69, 0x6C, 0xA9, 0xE6]);
+
+        let output = format_matrix(&matrix);
+        assert!(output.contains("  Ax   --   --   --   --   --   --   --   --   --   ok"));
+    }
+
+    #[test]
+    #[ignore]
+    fn single_step_nmos_test() {
+        run_suite_from_variable(NMOS_TESTS_VARIABLE, CpuVariant::Nmos6502);
+    }
+
+    #[test]
+    #[ignore]
+    fn single_step_cmos_test() {
+        run_suite_from_variable(CMOS_TESTS_VARIABLE, CpuVariant::W65C02S);
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::AddressBus;
+    use crate::cpu::{CpuVariant, StepOutcome, CPUFLAGS, IRQ_VECTOR};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    #[test]
+    fn tick_cycle_test() {
+        // LDA $1234
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xAD, 0x34, 0x12]);
+        cpu.bus.write(0x1234, 0x11);
+
+        for cycle in 1..=3 {
+            assert_eq!(cpu.tick_cycle().unwrap(), None);
+            assert_eq!(cpu.cycles(), cycle);
+            assert!(cpu.mid_instruction());
+        }
+
+        // A device changing memory between cycles is seen by the read on the last cycle
+        cpu.bus.write(0x1234, 0x22);
+        assert_eq!(cpu.tick_cycle().unwrap(), Some(StepOutcome::Instruction(4)));
+        assert!(!cpu.mid_instruction());
+        assert_eq!(cpu.reg.ac, 0x22);
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 3);
+    }
+
+    #[test]
+    fn step_finishes_instruction_test() {
+        // JSR $0300
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0x20, 0x00, 0x03]);
+        assert_eq!(cpu.tick_cycle().unwrap(), None);
+        assert_eq!(cpu.tick_cycle().unwrap(), None);
+
+        // The cycles run by tick_cycle() count towards the instruction
+        assert_eq!(cpu.step().unwrap(), StepOutcome::Instruction(6));
+        assert_eq!(cpu.reg.pc, 0x300);
+        assert_eq!(cpu.cycles(), 6);
+    }
+
+    #[test]
+    fn halted_tick_cycle_test() {
+        // NOP
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA]);
+        cpu.stall(3);
+
+        // Every halted cycle is its own call
+        for cycle in 1..=3 {
+            assert_eq!(cpu.tick_cycle().unwrap(), None);
+            assert_eq!(cpu.cycles(), cycle);
+            assert_eq!(cpu.halted_cycles(), cycle);
+        }
+        assert_eq!(cpu.halted_at(), Some(0));
+
+        assert_eq!(cpu.tick_cycle().unwrap(), None);
+        assert_eq!(cpu.tick_cycle().unwrap(), Some(StepOutcome::Instruction(5)));
+        assert_eq!(cpu.reg.pc, PROGRAM_START + 1);
+    }
+
+    #[test]
+    fn irq_between_cycles_test() {
+        // CLI; INC $10; NOP
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0x58, 0xE6, 0x10, 0xEA],
+        );
+        cpu.bus.write(IRQ_VECTOR, 0x00);
+        cpu.bus.write(IRQ_VECTOR + 1, 0x03);
+        cpu.reg.ps.insert(CPUFLAGS::INT_DISABLE);
+        cpu.step().unwrap();
+
+        // Asserted during the INC, which finishes before the interrupt sequence starts
+        cpu.tick_cycle().unwrap();
+        cpu.tick_cycle().unwrap();
+        cpu.set_irq(true);
+        let mut outcomes = Vec::new();
+        while outcomes.len() < 2 {
+            if let Some(outcome) = cpu.tick_cycle().unwrap() {
+                outcomes.push(outcome);
+            }
+        }
+
+        assert_eq!(
+            outcomes,
+            vec![StepOutcome::Instruction(5), StepOutcome::Interrupt(7)]
+        );
+        assert_eq!(cpu.bus.read(0x10), 0x01);
+        assert_eq!(cpu.reg.pc, 0x300);
+    }
+
+    #[test]
+    fn one_bus_access_per_cycle_test() {
+        for variant in [CpuVariant::Nmos6502, CpuVariant::W65C02S] {
+            // SED; ADC #$01; LDX #$FF; INC $12FF,X; BNE $0200
+            let program = [0xF8, 0x69, 0x01, 0xA2, 0xFF, 0xFE, 0xFF, 0x12, 0xD0, 0xF6];
+            let mut cpu = cpu_with_program(variant, PROGRAM_START, &program);
+            cpu.set_bus_log(true);
+
+            // Covers page crossings, taken branches and the 65C02 decimal cycle
+            let mut instructions = 0;
+            while instructions < 10 {
+                let cycles = cpu.cycles();
+                if cpu.tick_cycle().unwrap().is_some() {
+                    instructions += 1;
+                }
+
+                assert_eq!(cpu.cycles(), cycles + 1);
+                let log = cpu.take_bus_log();
+                assert_eq!(log.len(), 1, "{:?}", log);
+                assert_eq!(log[0].cycle, cycles);
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{CPUFLAGS, MOS6502};
+    use crate::trace_diff::{self, DiffOptions, TraceDiffError};
+    use crate::tracer::{TraceFilter, Tracer};
+
+    // The first lines of nestest.log, PPU column included
+    const NESTEST_LOG: &str = "\
+C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10
+C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12
+C5F9  86 10     STX $10 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 45 CYC:15
+C5FB  86 11     STX $11 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 54 CYC:18
+C5FD  20 2D C7  JSR $C72D                       A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 63 CYC:21
+C72D  EA        NOP                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 81 CYC:27
+C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 87 CYC:29
+C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 93 CYC:31
+";
+
+    fn nestest_trace(program_patch: Option<(u16, u8)>) -> String {
+        let mut cpu = MOS6502::new(MemoryBank::new());
+        let code: [(u16, &[u8]); 3] = [
+            (0xC000, &[0x4C, 0xF5, 0xC5]),
+            (
+                0xC5F5,
+                &[
+                    0xA2, 0x00, 0x86, 0x00, 0x86, 0x10, 0x86, 0x11, 0x20, 0x2D, 0xC7,
+                ],
+            ),
+            (0xC72D, &[0xEA, 0x38, 0xB0, 0x04]),
+        ];
+        for (start, bytes) in code {
+            for (i, byte) in bytes.iter().enumerate() {
+                cpu.bus.write(start + i as u16, *byte);
+            }
+        }
+        if let Some((address, value)) = program_patch {
+            cpu.bus.write(address, value);
+        }
+        cpu.set_pc(0xC000);
+        cpu.reg.sp = 0xFD;
+        cpu.reg.ps = CPUFLAGS::from_bits_retain(0x24);
+
+        let mut tracer = Tracer::new(Vec::new(), TraceFilter::default());
+        for _ in 0..9 {
+            tracer.trace(&mut cpu).unwrap();
+            cpu.step().unwrap();
+        }
+        String::from_utf8(tracer.into_inner()).unwrap()
+    }
+
+    fn diff(left: &str, right: &str, options: &DiffOptions) -> Option<trace_diff::Divergence> {
+        trace_diff::diff_traces(left.as_bytes(), right.as_bytes(), options).unwrap()
+    }
+
+    #[test]
+    fn matching_traces_test() {
+        // The CPU starts counting at 0, nestest.log at 7
+        let trace = nestest_trace(None);
+        let options = DiffOptions {
+            relative_cycles: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(diff(&trace, NESTEST_LOG, &options), None);
+
+        let divergence = diff(&trace, NESTEST_LOG, &DiffOptions::default()).unwrap();
+        assert_eq!(divergence.line, 1);
+        assert_eq!(divergence.fields, vec!["CYC"]);
+    }
+
+    #[test]
+    fn first_divergence_test() {
+        // LDX #$01 instead of LDX #$00, which also leaves Z clear
+        let trace = nestest_trace(Some((0xC5F6, 0x01)));
+        let options = DiffOptions {
+            context: 2,
+            relative_cycles: true,
+        };
+        let divergence = diff(&trace, NESTEST_LOG, &options).unwrap();
+
+        assert_eq!(divergence.line, 3);
+        assert_eq!(divergence.fields, vec!["X", "P"]);
+        assert_eq!(divergence.before.len(), 2);
+        assert!(divergence.before[1].starts_with("C5F5  A2 01"));
+        assert_eq!(divergence.left.len(), 3);
+        assert!(divergence.right[0].starts_with("C5F7  86 00"));
+
+        let report = divergence.to_string();
+        assert!(report.starts_with("Traces differ at line 3 (X, P)\n"));
+        assert!(report.contains("\n-        3  C5F7  86 00     STX $00 = 00"));
+        assert!(report.contains("\n+        5  C5FB"));
+    }
+
+    #[test]
+    fn end_of_trace_test() {
+        let trace = nestest_trace(None);
+        let short: String = trace
+            .lines()
+            .take(4)
+            .map(|line| line.to_owned() + "\n")
+            .collect();
+        let options = DiffOptions {
+            context: 1,
+            relative_cycles: true,
+        };
+
+        let divergence = diff(&short, NESTEST_LOG, &options).unwrap();
+        assert_eq!(divergence.line, 5);
+        assert_eq!(divergence.fields, vec!["end of trace"]);
+        assert!(divergence.left.is_empty());
+        assert_eq!(divergence.right.len(), 2);
+    }
+
+    #[test]
+    fn unparsable_line_test() {
+        let result = trace_diff::diff_traces(
+            NESTEST_LOG.as_bytes(),
+            "C000 ok\n\nnot a trace\n".as_bytes(),
+            &DiffOptions::default(),
+        );
+        match result {
+            Err(TraceDiffError::Unparsable { trace, line, .. }) => {
+                assert_eq!((trace, line), ("right", 2))
+            }
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_field_test() {
+        let result = trace_diff::diff_traces(
+            "C000 A:00 X:00 CYC:7\nC002 A:ZZ X:00 CYC:9\n".as_bytes(),
+            "C000 A:00 X:00 CYC:7\nC002 A:00 X:00 CYC:9\n".as_bytes(),
+            &DiffOptions::default(),
+        );
+        match result {
+            Err(TraceDiffError::Unparsable { trace, line, text }) => {
+                assert_eq!((trace, line), ("left", 2));
+                assert!(text.contains("A:ZZ"));
+            }
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, CPUFLAGS, IRQ_VECTOR, MOS6502};
+    use crate::tests::cpu_with_program;
+    use crate::tracer::{self, TraceFilter, Tracer};
+
+    fn traced_cpu(start: u16, program: &[u8]) -> MOS6502<MemoryBank> {
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, start, program);
+        cpu.reg.sp = 0xFD;
+        cpu.reg.ps = CPUFLAGS::from_bits_retain(0x24);
+        cpu
+    }
+
+    fn run_traced(cpu: &mut MOS6502<MemoryBank>, steps: usize, filter: TraceFilter) -> String {
+        let mut tracer = Tracer::new(Vec::new(), filter);
+        for _ in 0..steps {
+            tracer.trace(cpu).unwrap();
+            cpu.step().unwrap();
+        }
+        String::from_utf8(tracer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn nestest_format_test() {
+        // The start of nestest, JMP $C5F5 then LDX #$00, STX $00, JSR $C72D, NOP, SEC, BCS
+        let mut cpu = traced_cpu(0xC000, &[0x4C, 0xF5, 0xC5]);
+        for (address, byte) in [0xA2, 0x00, 0x86, 0x00, 0x20, 0x2D, 0xC7]
+            .iter()
+            .enumerate()
+        {
+            cpu.bus.write(0xC5F5 + address as u16, *byte);
+        }
+        for (address, byte) in [0xEA, 0x38, 0xB0, 0x04].iter().enumerate() {
+            cpu.bus.write(0xC72D + address as u16, *byte);
+        }
+
+        let trace = run_traced(&mut cpu, 7, TraceFilter::default());
+        let expected = "\
+C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:0
+C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD CYC:3
+C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD CYC:5
+C5F9  20 2D C7  JSR $C72D                       A:00 X:00 Y:00 P:26 SP:FD CYC:8
+C72D  EA        NOP                             A:00 X:00 Y:00 P:26 SP:FB CYC:14
+C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB CYC:16
+C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB CYC:18
+";
+        assert_eq!(trace, expected);
+    }
+
+    #[test]
+    fn operand_test() {
+        let mut cpu = traced_cpu(0x200, &[]);
+        cpu.reg.ix = 0x02;
+        cpu.reg.iy = 0x10;
+        cpu.bus.write(0x80, 0x00);
+        cpu.bus.write(0x81, 0x03);
+        cpu.bus.write(0x0300, 0x5A);
+        cpu.bus.write(0x0310, 0x89);
+        cpu.bus.write(0x02FF, 0x00);
+        cpu.bus.write(0x0200, 0xA9);
+        let cases: [(&[u8], &str); 8] = [
+            (&[0xA1, 0x7E], "A1 7E     LDA ($7E,X) @ 80 = 0300 = 5A"),
+            (&[0xB1, 0x80], "B1 80     LDA ($80),Y = 0300 @ 0310 = 89"),
+            (&[0xB5, 0x7E], "B5 7E     LDA $7E,X @ 80 = 00"),
+            (&[0xBD, 0x00, 0x03], "BD 00 03  LDA $0300,X @ 0302 = 00"),
+            (&[0x6C, 0xFF, 0x02], "6C FF 02  JMP ($02FF) = A900"),
+            (&[0x4A], "4A        LSR A"),
+            (&[0x04, 0x80], "04 80    *NOP $80 = 00"),
+            (&[0xE7, 0x80], "E7 80    *ISB $80 = 00"),
+        ];
+
+        for (program, expected) in cases {
+            for (i, byte) in program.iter().enumerate() {
+                cpu.bus.write(0x400 + i as u16, *byte);
+            }
+            cpu.reg.pc = 0x400;
+            let line = tracer::trace_line(&mut cpu);
+            assert_eq!(line[6..].split("  A:").next().unwrap().trim_end(), expected);
+        }
+    }
+
+    // Memory with a status register that clears when read, like a UART or VIA flag
+    struct StatusBus {
+        memory: MemoryBank,
+        status: u8,
+    }
+
+    const STATUS_ADDRESS: u16 = 0x4000;
+
+    impl AddressBus for StatusBus {
+        fn read(&mut self, address: u16) -> u8 {
+            if address == STATUS_ADDRESS {
+                return std::mem::take(&mut self.status);
+            }
+            self.memory.read(address)
+        }
+
+        fn write(&mut self, address: u16, value: u8) {
+            self.memory.write(address, value)
+        }
+
+        fn peek(&mut self, address: u16) -> u8 {
+            if address == STATUS_ADDRESS {
+                return self.status;
+            }
+            self.memory.read(address)
+        }
+    }
+
+    #[test]
+    fn side_effect_free_test() {
+        // LDA $4000
+        let mut bus = StatusBus {
+            memory: MemoryBank::new(),
+            status: 0x80,
+        };
+        for (i, byte) in [0xAD, 0x00, 0x40].iter().enumerate() {
+            bus.memory.write(0x200 + i as u16, *byte);
+        }
+        let mut cpu = MOS6502::new(bus);
+        cpu.set_pc(0x200);
+
+        let line = tracer::trace_line(&mut cpu);
+        assert!(line.starts_with("0200  AD 00 40  LDA $4000 = 80"));
+        assert_eq!(cpu.bus.status, 0x80);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.reg.ac, 0x80);
+        assert_eq!(cpu.bus.status, 0x00);
+    }
+
+    #[test]
+    fn filter_test() {
+        // INX, JMP $0200
+        let mut cpu = traced_cpu(0x200, &[0xE8, 0x4C, 0x00, 0x02]);
+
+        let filter = TraceFilter {
+            addresses: Some(0x200..=0x200),
+            instructions: None,
+        };
+        let trace = run_traced(&mut cpu, 6, filter);
+        assert_eq!(trace.lines().count(), 3);
+        assert!(trace.lines().all(|line| line.starts_with("0200  E8")));
+
+        let filter = TraceFilter {
+            addresses: None,
+            instructions: Some(2..5),
+        };
+        let trace = run_traced(&mut cpu, 6, filter);
+        let pcs: Vec<&str> = trace.lines().map(|line| &line[..4]).collect();
+        assert_eq!(pcs, vec!["0200", "0201", "0200"]);
+    }
+
+    #[test]
+    fn interrupt_not_traced_test() {
+        // CLI, NOP
+        let mut cpu = traced_cpu(0x200, &[0x58, 0xEA]);
+        cpu.bus.write(IRQ_VECTOR, 0x00);
+        cpu.bus.write(IRQ_VECTOR + 1, 0x04);
+        cpu.bus.write(0x400, 0xEA);
+        cpu.step().unwrap();
+        cpu.set_irq(true);
+
+        // NOP, the IRQ sequence, then the NOP of the handler
+        let mut tracer = Tracer::new(Vec::new(), TraceFilter::default());
+        for _ in 0..3 {
+            tracer.trace(&mut cpu).unwrap();
+            cpu.step().unwrap();
+        }
+        assert_eq!(tracer.instructions(), 2);
+        let trace = String::from_utf8(tracer.into_inner()).unwrap();
+        let pcs: Vec<&str> = trace.lines().map(|line| &line[..4]).collect();
+        assert_eq!(pcs, vec!["0201", "0400"]);
+    }
+}
@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod tests {
+    use crate::address_bus::{AddressBus, MemoryBank};
+    use crate::cpu::{CpuVariant, Trap, TrapPolicy, CPUFLAGS, MOS6502};
+    use crate::tests::cpu_with_program;
+
+    const PROGRAM_START: u16 = 0x200;
+
+    // Steps until trapped, ignoring halts
+    fn run(cpu: &mut MOS6502<MemoryBank>, max_steps: usize) -> Option<Trap> {
+        for _ in 0..max_steps {
+            if cpu.is_trapped() {
+                break;
+            }
+            let _ = cpu.step();
+        }
+        cpu.trap()
+    }
+
+    #[test]
+    fn self_jump_test() {
+        // NOP; JMP $0201
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xEA, 0x4C, 0x01, 0x02],
+        );
+        assert_eq!(
+            run(&mut cpu, 10),
+            Some(Trap::SelfJump {
+                address: PROGRAM_START + 1
+            })
+        );
+
+        // JMP $0200 is a loop but not a trap
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xEA, 0x4C, 0x00, 0x02],
+        );
+        assert_eq!(run(&mut cpu, 10), None);
+
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xEA, 0x4C, 0x01, 0x02],
+        );
+        cpu.set_trap_policy(TrapPolicy {
+            self_jump: false,
+            ..TrapPolicy::default()
+        });
+        assert_eq!(run(&mut cpu, 10), None);
+    }
+
+    #[test]
+    fn self_branch_test() {
+        // BNE *, not taken since Z is set
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xD0, 0xFE, 0xEA]);
+        cpu.reg.ps.insert(CPUFLAGS::ZERO);
+        cpu.step().unwrap();
+        assert_eq!(cpu.trap(), None);
+
+        // BEQ *
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xF0, 0xFE]);
+        cpu.reg.ps.insert(CPUFLAGS::ZERO);
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.trap(),
+            Some(Trap::SelfBranch {
+                address: PROGRAM_START
+            })
+        );
+
+        // BBS0 $10,* is three bytes long
+        let mut cpu = cpu_with_program(CpuVariant::W65C02S, PROGRAM_START, &[0x8F, 0x10, 0xFD]);
+        cpu.bus.write(0x10, 0x01);
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.trap(),
+            Some(Trap::SelfBranch {
+                address: PROGRAM_START
+            })
+        );
+    }
+
+    #[test]
+    fn brk_and_jam_test() {
+        // NOP; BRK
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA, 0x00]);
+        assert_eq!(run(&mut cpu, 2), None);
+
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA, 0x00]);
+        cpu.set_trap_policy(TrapPolicy {
+            brk: true,
+            ..TrapPolicy::default()
+        });
+        assert_eq!(
+            run(&mut cpu, 2),
+            Some(Trap::Brk {
+                address: PROGRAM_START + 1
+            })
+        );
+
+        // NOP; JAM
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &[0xEA, 0x02]);
+        assert_eq!(
+            run(&mut cpu, 2),
+            Some(Trap::Jam {
+                address: PROGRAM_START + 1
+            })
+        );
+    }
+
+    #[test]
+    fn exit_write_test() {
+        // LDA #$2A; STA $F001
+        let mut cpu = cpu_with_program(
+            CpuVariant::Nmos6502,
+            PROGRAM_START,
+            &[0xA9, 0x2A, 0x8D, 0x01, 0xF0],
+        );
+        cpu.set_trap_policy(TrapPolicy {
+            exit_address: Some(0xF001),
+            ..TrapPolicy::default()
+        });
+        assert_eq!(
+            run(&mut cpu, 10),
+            Some(Trap::ExitWrite {
+                address: PROGRAM_START + 2,
+                value: 0x2A
+            })
+        );
+    }
+
+    #[test]
+    fn pc_and_budget_test() {
+        // NOP x 4; JMP $0200
+        let program = [0xEA, 0xEA, 0xEA, 0xEA, 0x4C, 0x00, 0x02];
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &program);
+        cpu.set_trap_policy(TrapPolicy {
+            pcs: [0x203, 0x300].into_iter().collect(),
+            ..TrapPolicy::default()
+        });
+        assert_eq!(run(&mut cpu, 10), Some(Trap::Pc { address: 0x203 }));
+
+        // Trapped conditions are cleared by moving PC
+        cpu.set_pc(PROGRAM_START);
+        assert_eq!(cpu.trap(), None);
+
+        let mut cpu = cpu_with_program(CpuVariant::Nmos6502, PROGRAM_START, &program);
+        cpu.set_trap_policy(TrapPolicy {
+            cycle_budget: Some(20),
+            ..TrapPolicy::default()
+        });
+
+        // 2 + 2 + 2 + 2 + 3 + 2 + 2 + 2 + 2 + 3 cycles, ending on the second JMP
+        assert_eq!(
+            run(&mut cpu, 100),
+            Some(Trap::CycleBudget {
+                address: PROGRAM_START + 4,
+                cycles: 22
+            })
+        );
+    }
+}
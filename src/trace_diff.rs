@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead};
+
+// The fields compared on every line, PC is the first four characters and the rest are
+// "NAME:value" columns. Anything else on the line (bytes, disassembly, a PPU column) is ignored
+const FIELDS: [&str; 7] = ["PC", "A", "X", "Y", "P", "SP", "CYC"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    pub context: usize,        // Lines shown before and after the first difference
+    pub relative_cycles: bool, // Compare cycles counted from the first line of each trace
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: 5,
+            relative_cycles: false,
+        }
+    }
+}
+
+// Where two traces first differ. Lines are counted from 1, skipping blank lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub line: usize,
+    pub fields: Vec<&'static str>, // What differs, "end of trace" when one stops early
+    pub before: Vec<String>,       // Lines before it, the same in both traces
+    pub left: Vec<String>,         // The differing line and the ones after it
+    pub right: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Traces differ at line {} ({})",
+            self.line,
+            self.fields.join(", ")
+        )?;
+
+        let first = self.line - self.before.len();
+        for (i, line) in self.before.iter().enumerate() {
+            writeln!(f, "  {:>8}  {}", first + i, line)?;
+        }
+        for (i, line) in self.left.iter().enumerate() {
+            writeln!(f, "- {:>8}  {}", self.line + i, line)?;
+        }
+        for (i, line) in self.right.iter().enumerate() {
+            writeln!(f, "+ {:>8}  {}", self.line + i, line)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum TraceDiffError {
+    Io(io::Error),
+    // A line that doesn't start with a PC or has a field with a malformed value, in the
+    // "left" or "right" trace
+    Unparsable {
+        trace: &'static str,
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for TraceDiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceDiffError::Io(error) => write!(f, "{}", error),
+            TraceDiffError::Unparsable { trace, line, text } => {
+                write!(
+                    f,
+                    "Can't parse line {} of the {} trace: {}",
+                    line, trace, text
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceDiffError {}
+
+impl From<io::Error> for TraceDiffError {
+    fn from(error: io::Error) -> Self {
+        TraceDiffError::Io(error)
+    }
+}
+
+type Fields = [Option<u64>; FIELDS.len()];
+
+fn parse_line(text: &str) -> Option<Fields> {
+    let mut fields: Fields = [None; FIELDS.len()];
+    fields[0] = Some(u16::from_str_radix(text.get(..4)?, 16).ok()? as u64);
+
+    for token in text.split_whitespace() {
+        let Some((name, value)) = token.split_once(':') else {
+            continue;
+        };
+        let Some(index) = FIELDS.iter().position(|&field| field == name) else {
+            continue;
+        };
+        let radix = if name == "CYC" { 10 } else { 16 };
+        fields[index] = Some(u64::from_str_radix(value, radix).ok()?);
+    }
+    Some(fields)
+}
+
+struct TraceLines<R: BufRead> {
+    name: &'static str,
+    lines: io::Lines<R>,
+    line: usize,
+    first_cycle: Option<u64>,
+    relative_cycles: bool,
+}
+
+impl<R: BufRead> TraceLines<R> {
+    fn new(name: &'static str, reader: R, relative_cycles: bool) -> Self {
+        Self {
+            name,
+            lines: reader.lines(),
+            line: 0,
+            first_cycle: None,
+            relative_cycles,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<(String, Fields)>, TraceDiffError> {
+        for text in self.lines.by_ref() {
+            let text = text?;
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            self.line += 1;
+            let Some(mut fields) = parse_line(&text) else {
+                return Err(TraceDiffError::Unparsable {
+                    trace: self.name,
+                    line: self.line,
+                    text,
+                });
+            };
+
+            let cycles = &mut fields[FIELDS.len() - 1];
+            if let (true, Some(cycle)) = (self.relative_cycles, *cycles) {
+                let first = *self.first_cycle.get_or_insert(cycle);
+                *cycles = Some(cycle.wrapping_sub(first));
+            }
+            return Ok(Some((text, fields)));
+        }
+        Ok(None)
+    }
+}
+
+// Compares two traces line by line, like the ones written by tracer::Tracer or
+// nestest.log, and returns the first line that differs. Fields missing from either line
+// aren't compared
+pub fn diff_traces(
+    left: impl BufRead,
+    right: impl BufRead,
+    options: &DiffOptions,
+) -> Result<Option<Divergence>, TraceDiffError> {
+    let mut left = TraceLines::new("left", left, options.relative_cycles);
+    let mut right = TraceLines::new("right", right, options.relative_cycles);
+    let mut before = VecDeque::new();
+
+    loop {
+        let (left_line, right_line) = (left.next()?, right.next()?);
+        let fields = match (&left_line, &right_line) {
+            (None, None) => return Ok(None),
+            (Some((_, left_fields)), Some((_, right_fields))) => (0..FIELDS.len())
+                .filter(|&i| match (left_fields[i], right_fields[i]) {
+                    (Some(left), Some(right)) => left != right,
+                    _ => false,
+                })
+                .map(|i| FIELDS[i])
+                .collect(),
+            _ => vec!["end of trace"],
+        };
+
+        if fields.is_empty() {
+            before.push_back(left_line.unwrap().0);
+            if before.len() > options.context {
+                before.pop_front();
+            }
+            continue;
+        }
+
+        let mut divergence = Divergence {
+            line: left.line.max(right.line),
+            fields,
+            before: before.into(),
+            left: left_line.into_iter().map(|(text, _)| text).collect(),
+            right: right_line.into_iter().map(|(text, _)| text).collect(),
+        };
+        for _ in 0..options.context {
+            if let Some((text, _)) = left.next()? {
+                divergence.left.push(text);
+            }
+            if let Some((text, _)) = right.next()? {
+                divergence.right.push(text);
+            }
+        }
+        return Ok(Some(divergence));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    address_bus::AddressBus,
+    cpu::{
+        opcode_modes::{self, AddressingMode},
+        opcode_table::{opcode_table, Opcode},
+        CPUFLAGS, MOS6502,
+    },
+    disassembler,
+};
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+// Which instructions a Tracer writes, everything by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraceFilter {
+    pub addresses: Option<RangeInclusive<u16>>, // Only instructions starting in this range
+    pub instructions: Option<Range<u64>>,       // Only these, counted from 0 as they run
+}
+
+// Writes a line per instruction in the format of nestest.log (Nintendulator), without the PPU
+// column:
+//
+//   C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:24 SP:FD CYC:13
+//
+// so traces can be diffed against other emulators
+pub struct Tracer<W: Write> {
+    output: W,
+    filter: TraceFilter,
+    instructions: u64, // Instructions seen so far, traced or not
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(output: W, filter: TraceFilter) -> Self {
+        Self {
+            output,
+            filter,
+            instructions: 0,
+        }
+    }
+
+    // Traces the instruction the CPU is about to run, call it before every step(). Interrupt
+    // sequences and the cycles after a JAM, STP or WAI aren't instructions and aren't traced
+    pub fn trace<B: AddressBus>(&mut self, cpu: &mut MOS6502<B>) -> io::Result<()> {
+        if !cpu.next_is_instruction() {
+            return Ok(());
+        }
+
+        let instruction = self.instructions;
+        self.instructions += 1;
+        if let Some(addresses) = &self.filter.addresses {
+            if !addresses.contains(&cpu.reg.pc) {
+                return Ok(());
+            }
+        }
+        if let Some(instructions) = &self.filter.instructions {
+            if !instructions.contains(&instruction) {
+                return Ok(());
+            }
+        }
+
+        writeln!(self.output, "{}", trace_line(cpu))
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+// The trace line of the instruction at PC, with the registers and cycle count before it runs.
+// The values shown for memory operands are peeked so tracing doesn't disturb I/O devices
+pub fn trace_line<B: AddressBus>(cpu: &mut MOS6502<B>) -> String {
+    let address = cpu.reg.pc;
+    let variant = cpu.variant();
+    let opcode = cpu.bus.peek(address);
+    let Opcode { mnemonic, mode, .. } = opcode_table::<B>(variant)[opcode as usize];
+
+    let bytes: Vec<String> = (0..disassembler::instruction_length(mode))
+        .map(|i| format!("{:02X}", cpu.bus.peek(address.wrapping_add(i as u16))))
+        .collect();
+    let undocumented = if opcode_modes::is_documented(opcode, variant) {
+        ' '
+    } else {
+        '*'
+    };
+
+    // nestest.log calls ISC ISB
+    let mnemonic = if mnemonic == "ISC" { "ISB" } else { mnemonic };
+    let operand = decode_operand(cpu, mnemonic, mode, address);
+    let instruction = format!("{} {}", mnemonic, operand);
+
+    // B doesn't exist in the register and the unused bit always reads as set
+    let status = (cpu.reg.ps - CPUFLAGS::BREAK) | CPUFLAGS::UNUSED;
+    format!(
+        "{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+        address,
+        bytes.join(" "),
+        undocumented,
+        instruction.trim_end(),
+        cpu.reg.ac,
+        cpu.reg.ix,
+        cpu.reg.iy,
+        status.bits(),
+        cpu.reg.sp,
+        cpu.cycles()
+    )
+}
+
+fn read_word<B: AddressBus>(cpu: &mut MOS6502<B>, address: u16) -> u16 {
+    cpu.bus.peek(address) as u16 | ((cpu.bus.peek(address.wrapping_add(1)) as u16) << 8)
+}
+
+// Pointers in the zero page wrap around within it
+fn read_zero_page_word<B: AddressBus>(cpu: &mut MOS6502<B>, address: u8) -> u16 {
+    cpu.bus.peek(address as u16) as u16
+        | ((cpu.bus.peek(address.wrapping_add(1) as u16) as u16) << 8)
+}
+
+// Branch offsets are relative to the end of the instruction
+fn branch_target(address: u16, length: u16, offset: u8) -> u16 {
+    address
+        .wrapping_add(length)
+        .wrapping_add(offset as i8 as u16)
+}
+
+// Operands in nestest.log syntax, which also shows the effective address and the value there
+fn decode_operand<B: AddressBus>(
+    cpu: &mut MOS6502<B>,
+    mnemonic: &str,
+    mode: AddressingMode,
+    address: u16,
+) -> String {
+    let byte = cpu.bus.peek(address.wrapping_add(1));
+    let word = read_word(cpu, address.wrapping_add(1));
+
+    match mode {
+        AddressingMode::Implied => String::new(),
+        AddressingMode::Accumulator => String::from("A"),
+        AddressingMode::Immediate => format!("#${:02X}", byte),
+        AddressingMode::Relative => format!("${:04X}", branch_target(address, 2, byte)),
+        AddressingMode::ZeroPageRelative => {
+            let offset = cpu.bus.peek(address.wrapping_add(2));
+            let value = cpu.bus.peek(byte as u16);
+            format!(
+                "${:02X} = {:02X},${:04X}",
+                byte,
+                value,
+                branch_target(address, 3, offset)
+            )
+        }
+
+        AddressingMode::ZeroPage => {
+            format!("${:02X} = {:02X}", byte, cpu.bus.peek(byte as u16))
+        }
+        AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+            let (index, name) = match mode {
+                AddressingMode::ZeroPageX => (cpu.reg.ix, 'X'),
+                _ => (cpu.reg.iy, 'Y'),
+            };
+            let effective = byte.wrapping_add(index);
+            let value = cpu.bus.peek(effective as u16);
+            format!("${:02X},{} @ {:02X} = {:02X}", byte, name, effective, value)
+        }
+
+        AddressingMode::Absolute if mnemonic == "JMP" || mnemonic == "JSR" => {
+            format!("${:04X}", word)
+        }
+        AddressingMode::Absolute => format!("${:04X} = {:02X}", word, cpu.bus.peek(word)),
+        AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+            let (index, name) = match mode {
+                AddressingMode::AbsoluteX => (cpu.reg.ix, 'X'),
+                _ => (cpu.reg.iy, 'Y'),
+            };
+            let effective = word.wrapping_add(index as u16);
+            let value = cpu.bus.peek(effective);
+            format!("${:04X},{} @ {:04X} = {:02X}", word, name, effective, value)
+        }
+
+        AddressingMode::Indirect => {
+            // The NMOS 6502 doesn't carry into the high byte of the pointer
+            let high = if cpu.variant().is_cmos() {
+                word.wrapping_add(1)
+            } else {
+                (word & 0xFF00) | (word.wrapping_add(1) & 0x00FF)
+            };
+            let target = cpu.bus.peek(word) as u16 | ((cpu.bus.peek(high) as u16) << 8);
+            format!("(${:04X}) = {:04X}", word, target)
+        }
+        AddressingMode::AbsoluteIndexedIndirect => {
+            let target = read_word(cpu, word.wrapping_add(cpu.reg.ix as u16));
+            format!("(${:04X},X) = {:04X}", word, target)
+        }
+        AddressingMode::IndirectX => {
+            let pointer = byte.wrapping_add(cpu.reg.ix);
+            let effective = read_zero_page_word(cpu, pointer);
+            let value = cpu.bus.peek(effective);
+            format!(
+                "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
+                byte, pointer, effective, value
+            )
+        }
+        AddressingMode::IndirectY => {
+            let base = read_zero_page_word(cpu, byte);
+            let effective = base.wrapping_add(cpu.reg.iy as u16);
+            let value = cpu.bus.peek(effective);
+            format!(
+                "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+                byte, base, effective, value
+            )
+        }
+        AddressingMode::ZeroPageIndirect => {
+            let effective = read_zero_page_word(cpu, byte);
+            let value = cpu.bus.peek(effective);
+            format!("(${:02X}) = {:04X} = {:02X}", byte, effective, value)
+        }
+    }
+}